@@ -0,0 +1,372 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, LitBool, LitInt, LitStr, Token};
+
+pub struct Enum {
+	ident: syn::Ident,
+	variants: Vec<Variant>,
+}
+
+impl Enum {
+	/// match arms for an optional value, returning `None` for variants without one.
+	fn optional<F>(&self, signature: TokenStream, value: F) -> TokenStream
+	where
+		F: Fn(&Variant) -> Option<TokenStream>,
+	{
+		let arms = self
+			.variants
+			.iter()
+			.filter_map(|variant| {
+				let ident = &variant.ident;
+				let value = value(variant)?;
+				Some(quote! {
+					Self::#ident { .. } => ::core::option::Option::Some(#value),
+				})
+			})
+			.collect::<Vec<_>>();
+
+		if arms.is_empty() {
+			return quote! {};
+		}
+
+		quote! {
+			#signature {
+				#[allow(unreachable_patterns)]
+				match self {
+					#(#arms)*
+					_ => ::core::option::Option::None,
+				}
+			}
+		}
+	}
+
+	fn exit_code(&self) -> TokenStream {
+		let signature = quote! { fn exit_code(&self) -> ::core::option::Option<u8> };
+		self.optional(signature, |variant| {
+			let exit_code = variant.attrs.exit_code?;
+			Some(quote! { #exit_code })
+		})
+	}
+
+	fn status(&self) -> TokenStream {
+		let signature = quote! { fn status(&self) -> ::core::option::Option<u16> };
+		self.optional(signature, |variant| {
+			let status = variant.attrs.status?;
+			Some(quote! { #status })
+		})
+	}
+
+	fn canonical(&self) -> TokenStream {
+		let signature = quote! {
+			fn canonical(&self) -> ::core::option::Option<::mayerror::Canonical>
+		};
+		self.optional(signature, |variant| {
+			let canonical = variant.attrs.canonical.as_ref()?;
+			Some(quote! { ::mayerror::Canonical::#canonical })
+		})
+	}
+
+	fn is_retryable(&self) -> TokenStream {
+		let signature = quote! { fn is_retryable(&self) -> ::core::option::Option<bool> };
+		self.optional(signature, |variant| {
+			match (&variant.attrs.retryable, &variant.attrs.retry_after) {
+				(Some(Retryable::Bool(retryable)), _) => Some(quote! { #retryable }),
+				(Some(Retryable::Source), _) => Some(quote! { ::mayerror::is_transient(self) }),
+				(None, Some(_)) => Some(quote! { true }),
+				(None, None) => None,
+			}
+		})
+	}
+
+	fn retry_after(&self) -> TokenStream {
+		let signature = quote! {
+			fn retry_after(&self) -> ::core::option::Option<::core::time::Duration>
+		};
+		self.optional(signature, |variant| {
+			let (secs, nanos) = variant.attrs.retry_after?;
+			Some(quote! { ::core::time::Duration::new(#secs, #nanos) })
+		})
+	}
+
+	fn capture(&self) -> TokenStream {
+		let signature = quote! {
+			fn capture(&self) -> ::core::option::Option<::mayerror::CapturePolicy>
+		};
+		self.optional(signature, |variant| {
+			let Capture { policy, arg } = variant.attrs.capture.as_ref()?;
+			let arg = arg.as_ref().map(|arg| quote! { (#arg) });
+			Some(quote! { ::mayerror::CapturePolicy::#policy #arg })
+		})
+	}
+
+	fn id(&self) -> TokenStream {
+		if self.variants.is_empty() {
+			return quote! {};
+		}
+
+		let arms = self.variants.iter().map(|variant| {
+			let ident = &variant.ident;
+			let id = match &variant.attrs.id {
+				Some(id) => id.value(),
+				None => kebab_case(&ident.to_string()),
+			};
+
+			quote! {
+				Self::#ident { .. } => #id,
+			}
+		});
+
+		quote! {
+			fn id(&self) -> &'static str {
+				match self {
+					#(#arms)*
+				}
+			}
+		}
+	}
+
+	pub fn code(&self) -> TokenStream {
+		let ident = &self.ident;
+		let exit_code = self.exit_code();
+		let status = self.status();
+		let canonical = self.canonical();
+		let is_retryable = self.is_retryable();
+		let retry_after = self.retry_after();
+		let capture = self.capture();
+		let id = self.id();
+
+		quote! {
+			impl ::mayerror::Code for #ident {
+				#id
+				#exit_code
+				#status
+				#canonical
+				#is_retryable
+				#retry_after
+				#capture
+			}
+		}
+	}
+}
+
+/// `FileNotFound` -> `file-not-found`, `IOError` -> `io-error`
+fn kebab_case(ident: &str) -> String {
+	let chars = ident.chars().collect::<Vec<_>>();
+	let mut kebab = String::with_capacity(ident.len() + 4);
+
+	for (idx, &ch) in chars.iter().enumerate() {
+		if ch == '_' {
+			kebab.push('-');
+			continue;
+		}
+
+		if ch.is_uppercase() && idx > 0 {
+			let prev = chars[idx - 1];
+			let next = chars.get(idx + 1).copied();
+			let word_start = prev.is_lowercase()
+				|| prev.is_ascii_digit()
+				|| (prev.is_uppercase() && next.is_some_and(char::is_lowercase));
+			if word_start && !kebab.ends_with('-') {
+				kebab.push('-');
+			}
+		}
+
+		kebab.extend(ch.to_lowercase());
+	}
+
+	kebab
+}
+
+impl Enum {
+	pub fn from_syn(ast: DeriveInput) -> Result<Self, syn::Error> {
+		let Data::Enum(data) = ast.data else {
+			return Err(syn::Error::new_spanned(
+				ast,
+				"#[derive(Code)] is only supported for enums",
+			));
+		};
+
+		let ident = ast.ident;
+		let variants = data
+			.variants
+			.into_iter()
+			.map(Variant::from_syn)
+			.collect::<Result<Vec<_>, _>>()?;
+
+		Ok(Enum { ident, variants })
+	}
+}
+
+struct Variant {
+	ident: syn::Ident,
+	attrs: Attrs,
+}
+
+impl Variant {
+	fn from_syn(variant: syn::Variant) -> Result<Self, syn::Error> {
+		let attrs = Attrs::from_syn(&variant.attrs)?;
+		let ident = variant.ident;
+
+		Ok(Variant { ident, attrs })
+	}
+}
+
+#[derive(Default)]
+struct Attrs {
+	exit_code: Option<u8>,
+	status: Option<u16>,
+	id: Option<LitStr>,
+	canonical: Option<syn::Ident>,
+	retryable: Option<Retryable>,
+	retry_after: Option<(u64, u32)>,
+	capture: Option<Capture>,
+}
+
+enum Retryable {
+	Bool(bool),
+	Source,
+}
+
+/// a variant of `CapturePolicy`, like `Never` or `Sampled(100)`
+struct Capture {
+	policy: syn::Ident,
+	arg: Option<LitInt>,
+}
+
+impl Attrs {
+	fn from_syn(attrs: &[syn::Attribute]) -> Result<Self, syn::Error> {
+		let mut this = Attrs::default();
+
+		for attr in attrs {
+			if !attr.path().is_ident("mayerror") {
+				continue;
+			}
+
+			attr.parse_nested_meta(|meta| {
+				if meta.path.is_ident("exit_code") {
+					if this.exit_code.is_some() {
+						return Err(meta.error("exit_code is already defined"));
+					}
+
+					let lit = meta.value()?.parse::<LitInt>()?;
+					let exit_code = lit.base10_parse()?;
+					if exit_code == 0 {
+						return Err(syn::Error::new_spanned(
+							lit,
+							"exit_code can't be 0, which means success",
+						));
+					}
+
+					this.exit_code = Some(exit_code);
+				} else if meta.path.is_ident("status") {
+					if this.status.is_some() {
+						return Err(meta.error("status is already defined"));
+					}
+
+					let lit = meta.value()?.parse::<LitInt>()?;
+					let status = lit.base10_parse()?;
+					if !(100..=599).contains(&status) {
+						return Err(syn::Error::new_spanned(
+							lit,
+							"status has to be between 100 and 599",
+						));
+					}
+
+					this.status = Some(status);
+				} else if meta.path.is_ident("id") {
+					if this.id.is_some() {
+						return Err(meta.error("id is already defined"));
+					}
+
+					this.id = Some(meta.value()?.parse()?);
+				} else if meta.path.is_ident("canonical") {
+					if this.canonical.is_some() {
+						return Err(meta.error("canonical is already defined"));
+					}
+
+					this.canonical = Some(meta.value()?.parse()?);
+				} else if meta.path.is_ident("retryable") {
+					if this.retryable.is_some() {
+						return Err(meta.error("retryable is already defined"));
+					}
+
+					let retryable = if meta.input.peek(Token![=]) {
+						let value = meta.value()?;
+						if value.peek(LitBool) {
+							Retryable::Bool(value.parse::<LitBool>()?.value)
+						} else {
+							let ident = value.parse::<syn::Ident>()?;
+							if ident != "source" {
+								return Err(syn::Error::new_spanned(
+									ident,
+									"expected `true`, `false` or `source`",
+								));
+							}
+							Retryable::Source
+						}
+					} else {
+						Retryable::Bool(true)
+					};
+
+					this.retryable = Some(retryable);
+				} else if meta.path.is_ident("retry_after") {
+					if this.retry_after.is_some() {
+						return Err(meta.error("retry_after is already defined"));
+					}
+
+					let lit = meta.value()?.parse::<LitStr>()?;
+					this.retry_after = Some(parse_duration(&lit)?);
+				} else if meta.path.is_ident("capture") {
+					if this.capture.is_some() {
+						return Err(meta.error("capture is already defined"));
+					}
+
+					let value = meta.value()?;
+					let policy = value.parse::<syn::Ident>()?;
+					let arg = if value.peek(syn::token::Paren) {
+						let content;
+						syn::parenthesized!(content in value);
+						Some(content.parse::<LitInt>()?)
+					} else {
+						None
+					};
+
+					this.capture = Some(Capture { policy, arg });
+				} else {
+					return Err(meta.error("unknown mayerror attribute"));
+				}
+
+				Ok(())
+			})?;
+		}
+
+		Ok(this)
+	}
+}
+
+/// parses durations like `500ms`, `5s`, `2m` or `1h` into seconds and nanoseconds
+fn parse_duration(lit: &LitStr) -> Result<(u64, u32), syn::Error> {
+	let value = lit.value();
+	let split = value
+		.find(|ch: char| !ch.is_ascii_digit())
+		.unwrap_or(value.len());
+	let (amount, unit) = value.split_at(split);
+
+	let error = || {
+		syn::Error::new_spanned(
+			lit,
+			"expected a duration like \"500ms\", \"5s\", \"2m\" or \"1h\"",
+		)
+	};
+	let amount = amount.parse::<u64>().map_err(|_| error())?;
+
+	let duration = match unit {
+		"ms" => (amount / 1000, (amount % 1000) as u32 * 1_000_000),
+		"s" => (amount, 0),
+		"m" => (amount.checked_mul(60).ok_or_else(error)?, 0),
+		"h" => (amount.checked_mul(60 * 60).ok_or_else(error)?, 0),
+		_ => return Err(error()),
+	};
+
+	Ok(duration)
+}
@@ -0,0 +1,111 @@
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::quote;
+use std::{
+	io,
+	path::{Path, PathBuf},
+};
+
+/// the same limit as for reading snippets, larger files wouldn't be shown anyway.
+const MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// the compressed `.rs` files of the workspace of the crate that is being compiled.
+pub struct Sources {
+	root: PathBuf,
+	files: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl Sources {
+	pub fn collect() -> Result<Self, syn::Error> {
+		let error = |err: io::Error| {
+			syn::Error::new(
+				Span::call_site(),
+				format!("failed to embed sources: {}", err),
+			)
+		};
+
+		let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").ok_or_else(|| {
+			syn::Error::new(
+				Span::call_site(),
+				"embed_sources! has to be compiled by cargo",
+			)
+		})?;
+		let root = workspace_root(Path::new(&manifest_dir));
+
+		let mut paths = Vec::new();
+		rust_files(&root, &mut paths).map_err(error)?;
+		paths.sort();
+
+		let mut files = Vec::new();
+		for path in paths {
+			let content = std::fs::read(&path).map_err(error)?;
+			let compressed = miniz_oxide::deflate::compress_to_vec(&content, 8);
+			files.push((path, compressed));
+		}
+
+		Ok(Sources { root, files })
+	}
+
+	pub fn embed(&self) -> TokenStream {
+		// `include_bytes!` makes cargo rebuild the crate when a file changes,
+		// and the unused constants don't end up in the binary. new files aren't
+		// tracked, as a proc macro can't depend on a directory on stable
+		let tracked = self.files.iter().map(|(path, _)| {
+			let path = path.to_string_lossy();
+			quote! { const _: &[u8] = ::core::include_bytes!(#path); }
+		});
+
+		let files = self.files.iter().map(|(path, compressed)| {
+			let relative = path.strip_prefix(&self.root).unwrap_or(path);
+			let relative = relative.to_string_lossy();
+			let compressed = Literal::byte_string(compressed);
+			quote! { (#relative, #compressed) }
+		});
+
+		quote! {
+			{
+				#(#tracked)*
+				::mayerror::EmbeddedSources::__new(&[#(#files),*])
+			}
+		}
+	}
+}
+
+/// the closest directory with a `Cargo.toml` that has a `[workspace]`, or the crate itself.
+fn workspace_root(manifest_dir: &Path) -> PathBuf {
+	let is_workspace = |dir: &Path| {
+		std::fs::read_to_string(dir.join("Cargo.toml"))
+			.is_ok_and(|manifest| manifest.lines().any(|line| line.trim() == "[workspace]"))
+	};
+
+	manifest_dir
+		.ancestors()
+		.find(|dir| is_workspace(dir))
+		.unwrap_or(manifest_dir)
+		.to_owned()
+}
+
+/// the `.rs` files in a directory, skipping build output and hidden directories.
+fn rust_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+	for entry in std::fs::read_dir(dir)? {
+		let entry = entry?;
+		let path = entry.path();
+		let file_type = entry.file_type()?;
+
+		if file_type.is_dir() {
+			let name = entry.file_name();
+			let name = name.to_string_lossy();
+			if name == "target" || name.starts_with('.') {
+				continue;
+			}
+
+			rust_files(&path, files)?;
+		} else if file_type.is_file()
+			&& path.extension().is_some_and(|ext| ext == "rs")
+			&& entry.metadata()?.len() <= MAX_FILE_SIZE
+		{
+			files.push(path);
+		}
+	}
+
+	Ok(())
+}
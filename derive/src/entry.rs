@@ -0,0 +1,197 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{
+	meta::ParseNestedMeta, spanned::Spanned, Expr, ExprLit, GenericArgument, ItemFn, Lit, LitStr,
+	PathArguments, ReturnType, Type, TypePath,
+};
+
+#[derive(Default)]
+pub struct Args {
+	theme: Option<TokenStream>,
+	verbosity: Option<TokenStream>,
+}
+
+impl Args {
+	pub fn parse(&mut self, meta: ParseNestedMeta) -> Result<(), syn::Error> {
+		if meta.path.is_ident("theme") {
+			if self.theme.is_some() {
+				return Err(meta.error("theme is already defined"));
+			}
+
+			let expr = meta.value()?.parse::<Expr>()?;
+			self.theme = Some(Args::theme(expr)?);
+		} else if meta.path.is_ident("verbosity") {
+			if self.verbosity.is_some() {
+				return Err(meta.error("verbosity is already defined"));
+			}
+
+			let expr = meta.value()?.parse::<Expr>()?;
+			self.verbosity = Some(Args::verbosity(expr)?);
+		} else {
+			return Err(meta.error("unknown argument, expected `theme` or `verbosity`"));
+		}
+
+		Ok(())
+	}
+
+	fn theme(expr: Expr) -> Result<TokenStream, syn::Error> {
+		let Some(lit) = lit_str(&expr) else {
+			return Ok(expr.into_token_stream());
+		};
+
+		match lit.value().as_str() {
+			"dark" => Ok(quote! { ::mayerror::Theme::dark() }),
+			"plain" => Ok(quote! { ::mayerror::Theme::new() }),
+			_ => Err(syn::Error::new_spanned(
+				lit,
+				"unknown theme, expected \"dark\" or \"plain\"",
+			)),
+		}
+	}
+
+	fn verbosity(expr: Expr) -> Result<TokenStream, syn::Error> {
+		let Some(lit) = lit_str(&expr) else {
+			return Ok(expr.into_token_stream());
+		};
+
+		match lit.value().as_str() {
+			"minimal" => Ok(quote! { ::mayerror::Verbosity::Minimal }),
+			"medium" => Ok(quote! { ::mayerror::Verbosity::Medium }),
+			"full" => Ok(quote! { ::mayerror::Verbosity::Full }),
+			_ => Err(syn::Error::new_spanned(
+				lit,
+				"unknown verbosity, expected \"minimal\", \"medium\" or \"full\"",
+			)),
+		}
+	}
+
+	fn config(&self) -> TokenStream {
+		let theme = self.theme.iter();
+		let verbosity = self.verbosity.iter();
+
+		quote! {
+			::mayerror::Config::new()
+				.first_party_crates([::core::env!("CARGO_CRATE_NAME")])
+				#(.theme(#theme))*
+				#(.verbosity(#verbosity))*
+		}
+	}
+}
+
+fn lit_str(expr: &Expr) -> Option<&LitStr> {
+	match expr {
+		Expr::Lit(ExprLit {
+			lit: Lit::Str(lit), ..
+		}) => Some(lit),
+		_ => None,
+	}
+}
+
+pub struct Main {
+	args: Args,
+	item: ItemFn,
+	/// the `E` of a `Result<(), E>` return type.
+	error: Option<Type>,
+}
+
+impl Main {
+	pub fn main(&self) -> TokenStream {
+		let ItemFn {
+			attrs,
+			vis,
+			sig,
+			block,
+		} = &self.item;
+		let ident = &sig.ident;
+		let config = self.args.config();
+
+		match (&sig.output, &self.error) {
+			(ReturnType::Type(_, ret), Some(error)) => {
+				// points errors about the error type not being a `MayError` at the signature
+				let report = quote_spanned! {error.span()=>
+					::mayerror::Report<#error>
+				};
+
+				quote! {
+					#(#attrs)*
+					#vis fn #ident() -> #report {
+						fn #ident() -> #ret #block
+
+						::mayerror::install_with(#config);
+						::mayerror::Report::from(#ident())
+					}
+				}
+			}
+			_ => quote! {
+				#(#attrs)*
+				#vis fn #ident() {
+					::mayerror::install_with(#config);
+					#block
+				}
+			},
+		}
+	}
+}
+
+impl Main {
+	pub fn from_syn(args: Args, item: ItemFn) -> Result<Self, syn::Error> {
+		let sig = &item.sig;
+		if let Some(asyncness) = &sig.asyncness {
+			return Err(syn::Error::new_spanned(
+				asyncness,
+				"#[mayerror::main] has to be placed below async runtime attributes like #[tokio::main]",
+			));
+		} else if !sig.generics.params.is_empty() {
+			return Err(syn::Error::new_spanned(
+				&sig.generics,
+				"#[mayerror::main] doesn't support generics",
+			));
+		} else if !sig.inputs.is_empty() {
+			return Err(syn::Error::new_spanned(
+				&sig.inputs,
+				"#[mayerror::main] doesn't support arguments",
+			));
+		}
+
+		let error = match &sig.output {
+			ReturnType::Default => None,
+			ReturnType::Type(_, ret) => match error_type(ret) {
+				Some(error) => Some(error.clone()),
+				None => {
+					return Err(syn::Error::new_spanned(
+						ret,
+						"#[mayerror::main] only supports returning nothing or `Result<(), E>`",
+					))
+				}
+			},
+		};
+
+		Ok(Main { args, item, error })
+	}
+}
+
+/// the `E` of a `Result<(), E>`.
+fn error_type(ret: &Type) -> Option<&Type> {
+	let Type::Path(TypePath { qself: None, path }) = ret else {
+		return None;
+	};
+
+	let segment = path.segments.last()?;
+	if segment.ident != "Result" {
+		return None;
+	}
+
+	let PathArguments::AngleBracketed(args) = &segment.arguments else {
+		return None;
+	};
+
+	let mut args = args.args.iter();
+	match (args.next(), args.next(), args.next()) {
+		(
+			Some(GenericArgument::Type(Type::Tuple(ok))),
+			Some(GenericArgument::Type(error)),
+			None,
+		) if ok.elems.is_empty() => Some(error),
+		_ => None,
+	}
+}
@@ -2,6 +2,11 @@ use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{spanned::Spanned, Data, DeriveInput, Index, Member, Type};
 
+mod code;
+#[cfg(feature = "embed-sources")]
+mod embed;
+mod entry;
+
 #[proc_macro_derive(MayError, attributes(code, location, backtrace))]
 pub fn mayerror_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 	let ast = match syn::parse::<DeriveInput>(input) {
@@ -18,16 +23,115 @@ pub fn mayerror_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStrea
 	let display = may_error.display();
 	let debug = may_error.debug();
 	let error = may_error.error();
+	let tonic = may_error.tonic();
+	let may_error = may_error.may_error();
 
 	quote! {
 		#from
 		#display
 		#debug
 		#error
+		#may_error
+		#tonic
 	}
 	.into()
 }
 
+#[proc_macro_derive(Code, attributes(mayerror))]
+pub fn code_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let ast = match syn::parse::<DeriveInput>(input) {
+		Ok(ast) => ast,
+		Err(err) => return err.to_compile_error().into(),
+	};
+
+	let code = match code::Enum::from_syn(ast) {
+		Ok(code) => code,
+		Err(err) => return err.to_compile_error().into(),
+	};
+
+	code.code().into()
+}
+
+/// compresses the `.rs` files of the workspace into the binary, so source snippets
+/// can be shown where the sources aren't available, like on another machine.
+///
+/// the files are read from the workspace of the crate that calls the macro,
+/// skipping `target` and hidden directories. pass the sources to
+/// `mayerror::Config::embedded_sources`.
+///
+/// the crate is rebuilt when an embedded file changes, but not when a file is added
+/// to the workspace. files added since the last build of the crate are only embedded
+/// once the file calling `embed_sources!` changes, or after a `cargo clean`.
+///
+/// ```ignore
+/// let config = mayerror::Config::new().embedded_sources(mayerror::embed_sources!());
+/// mayerror::install_with(config);
+/// ```
+#[cfg(feature = "embed-sources")]
+#[proc_macro]
+pub fn embed_sources(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	if !input.is_empty() {
+		let input = TokenStream::from(input);
+		return syn::Error::new_spanned(input, "embed_sources! doesn't take any arguments")
+			.to_compile_error()
+			.into();
+	}
+
+	match embed::Sources::collect() {
+		Ok(sources) => sources.embed().into(),
+		Err(err) => err.to_compile_error().into(),
+	}
+}
+
+/// sets up `mayerror` for the `main` function of a binary.
+///
+/// installs the panic hook and, if `main` returns a `Result<(), E>`,
+/// prints the error to stderr and exits with its exit code.
+///
+/// accepts `theme = "dark" | "plain"` and `verbosity = "minimal" | "medium" | "full"`,
+/// or any expression evaluating to a `mayerror::Theme` or `mayerror::Verbosity`.
+///
+/// ```ignore
+/// #[mayerror::main(theme = "plain", verbosity = "full")]
+/// fn main() -> Result<(), Error> {
+///     run()?;
+///     Ok(())
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn main(
+	args: proc_macro::TokenStream,
+	input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	let mut main_args = entry::Args::default();
+	let parser = syn::meta::parser(|meta| main_args.parse(meta));
+	if let Err(err) = syn::parse::Parser::parse(parser, args) {
+		return err.to_compile_error().into();
+	}
+
+	let item = match syn::parse::<syn::ItemFn>(input) {
+		Ok(item) => item,
+		Err(err) => return err.to_compile_error().into(),
+	};
+
+	let main = match entry::Main::from_syn(main_args, item) {
+		Ok(main) => main,
+		Err(err) => return err.to_compile_error().into(),
+	};
+
+	main.main().into()
+}
+
+/// the `mayerror::__private::Crate` of the crate that is being compiled.
+#[cfg(feature = "backtrace")]
+fn krate() -> TokenStream {
+	quote! {
+		::mayerror::__private::Crate {
+			name: ::core::env!("CARGO_CRATE_NAME"),
+		}
+	}
+}
+
 struct Struct {
 	fields: Fields,
 	ident: syn::Ident,
@@ -48,11 +152,13 @@ impl Struct {
 		}
 	}
 
-	#[cfg(feature = "backtrace")]
 	fn init_backtrace(&self) -> Option<(TokenStream, TokenStream)> {
 		if let Some(trace) = &self.fields.backtrace {
 			let body = quote! {
-				let backtrace = ::mayerror::__private::trace();
+				let backtrace = ::mayerror::__private::trace(
+					::mayerror::Code::capture(::mayerror::__private::metadata!(&code)),
+					::core::panic::Location::caller(),
+				);
 			};
 			let init = quote! {
 				#trace: backtrace,
@@ -66,20 +172,19 @@ impl Struct {
 	fn init(&self) -> TokenStream {
 		let ident = &self.ident;
 		let code = &self.fields.code;
+		let ty = &code.ty;
 
 		let (loc_body, loc_init) = self.init_loc().unzip();
 
-		#[cfg(feature = "backtrace")]
 		let (trace_body, trace_init) = self.init_backtrace().unzip();
-		#[cfg(not(feature = "backtrace"))]
-		let (trace_body, trace_init) = (quote! {}, quote! {});
 
 		quote! {
+			let code: #ty = ::core::convert::Into::into(value);
 			#loc_body
 			#trace_body
 
 			#ident {
-				#code: ::core::convert::Into::into(value),
+				#code: code,
 				#loc_init
 				#trace_init
 			}
@@ -92,12 +197,23 @@ impl Struct {
 
 		let init = self.init();
 
+		// `__private::trace` skips exactly one frame after itself, so this can't be inlined
+		#[cfg(feature = "backtrace")]
+		let inline = self
+			.fields
+			.backtrace
+			.as_ref()
+			.map(|_| quote! { #[inline(never)] });
+		#[cfg(not(feature = "backtrace"))]
+		let inline = quote! {};
+
 		quote! {
 			impl<T> ::core::convert::From<T> for #ident
 			where
 				T: ::core::convert::Into<#ty>
 			{
 				#[track_caller]
+				#inline
 				fn from(value: T) -> Self {
 					#init
 				}
@@ -134,7 +250,8 @@ impl Struct {
 		let cfield = &self.fields.code;
 
 		let error = quote! {
-			::core::write!(f, "{}", ::mayerror::__private::OwoColorize::red(&self.#cfield))?;
+			let theme = ::mayerror::__private::theme();
+			::core::write!(f, "{}", ::mayerror::__private::OwoColorize::style(&self.#cfield, ::mayerror::__private::styles::error(&theme)))?;
 		};
 
 		let source = quote! {
@@ -143,16 +260,40 @@ impl Struct {
 
 				::core::write!(f, "\n\nSource:")?;
 				for (idx, source) in chain.enumerate() {
-					let source = ::mayerror::__private::OwoColorize::magenta(&source);
+					let source = ::mayerror::__private::OwoColorize::style(&source, ::mayerror::__private::styles::source(&theme));
 					::core::write!(f, "\n{:4}: {}", idx, source)?;
 				}
 			}
 		};
 
+		// the location file is relative to the workspace, which is found in the backtrace
+		#[cfg(feature = "backtrace")]
+		let workspace = match &self.fields.backtrace {
+			Some(trace) => quote! {
+				::mayerror::__private::PrettyBacktrace::new(&self.#trace)
+					.location(::core::option::Option::Some(location))
+					.workspace()
+			},
+			None => quote! { ::core::option::Option::None },
+		};
+		#[cfg(feature = "backtrace")]
+		let snippet = quote! {
+			if ::mayerror::__private::verbosity() >= ::mayerror::Verbosity::Full {
+				if let ::core::option::Option::Some(location) = ::mayerror::MayError::location(self) {
+					let workspace = #workspace;
+					let source = ::mayerror::__private::Source::location(location).workspace(workspace);
+					::core::write!(f, "{}", source)?;
+				}
+			}
+		};
+		#[cfg(not(feature = "backtrace"))]
+		let snippet = quote! {};
+
 		let location = if let Some(location) = &self.fields.location {
 			quote! {
 				::core::write!(f, "\n\nLocation:")?;
-				::core::write!(f, "\n   {}", ::mayerror::__private::OwoColorize::cyan(&self.#location))?;
+				::core::write!(f, "\n   {}", ::mayerror::__private::OwoColorize::style(&self.#location, ::mayerror::__private::styles::location(&theme)))?;
+				#snippet
 			}
 		} else {
 			quote! {}
@@ -160,9 +301,15 @@ impl Struct {
 
 		#[cfg(feature = "backtrace")]
 		let backtrace = if let Some(trace) = &self.fields.backtrace {
+			let krate = krate();
+
 			quote! {
-				if *::mayerror::__private::VERBOSITY >= ::mayerror::__private::Verbosity::Medium {
-					let backtrace = ::mayerror::__private::PrettyBacktrace(&self.#trace);
+				if ::mayerror::__private::verbosity() >= ::mayerror::Verbosity::Medium {
+					let krate = #krate;
+					let location = ::mayerror::MayError::location(self);
+					let backtrace = ::mayerror::__private::PrettyBacktrace::new(&self.#trace)
+						.krate(krate)
+						.location(location);
 					::core::write!(f, "\n\n{}", backtrace)?;
 				}
 
@@ -208,6 +355,83 @@ impl Struct {
 			}
 		}
 	}
+
+	#[cfg(feature = "tonic")]
+	fn tonic(&self) -> TokenStream {
+		let ident = &self.ident;
+
+		quote! {
+			impl ::core::convert::From<#ident> for ::mayerror::__private::tonic::Status {
+				fn from(value: #ident) -> Self {
+					::mayerror::__private::tonic_status(&value)
+				}
+			}
+		}
+	}
+
+	#[cfg(not(feature = "tonic"))]
+	fn tonic(&self) -> TokenStream {
+		quote! {}
+	}
+
+	fn may_error(&self) -> TokenStream {
+		let ident = &self.ident;
+		let code = &self.fields.code;
+		let ty = &code.ty;
+
+		let location = if let Some(location) = &self.fields.location {
+			quote! { ::mayerror::__private::location!(&self.#location) }
+		} else {
+			quote! { ::core::option::Option::None }
+		};
+
+		let backtrace = if let Some(trace) = &self.fields.backtrace {
+			quote! { ::core::option::Option::Some(&self.#trace) }
+		} else {
+			quote! { ::core::option::Option::None }
+		};
+
+		#[cfg(feature = "backtrace")]
+		let frames = self.fields.backtrace.as_ref().map(|trace| {
+			let krate = krate();
+			quote! {
+				fn frames(&self) -> ::core::option::Option<::mayerror::Frames> {
+					let location = ::mayerror::MayError::location(self);
+					let frames = ::mayerror::__private::PrettyBacktrace::new(&self.#trace)
+						.krate(#krate)
+						.location(location)
+						.into_frames();
+					::core::option::Option::Some(frames)
+				}
+			}
+		});
+		#[cfg(not(feature = "backtrace"))]
+		let frames = quote! {};
+
+		quote! {
+			impl ::mayerror::MayError for #ident {
+				type Code = #ty;
+
+				fn code(&self) -> &Self::Code {
+					&self.#code
+				}
+
+				fn metadata(&self) -> &dyn ::mayerror::Code {
+					::mayerror::__private::metadata!(&self.#code)
+				}
+
+				fn location(&self) -> ::core::option::Option<&'static ::core::panic::Location<'static>> {
+					#location
+				}
+
+				fn backtrace(&self) -> ::core::option::Option<&::mayerror::Backtrace> {
+					#backtrace
+				}
+
+				#frames
+			}
+		}
+	}
 }
 
 impl Struct {
@@ -229,7 +453,6 @@ impl Struct {
 struct Fields {
 	code: Field,
 	location: Option<Field>,
-	#[cfg(feature = "backtrace")]
 	backtrace: Option<Field>,
 }
 
@@ -263,8 +486,6 @@ impl Fields {
 	fn from_syn(fields: syn::Fields) -> Result<Fields, syn::Error> {
 		let mut location = None;
 		let mut code = None;
-
-		#[cfg(feature = "backtrace")]
 		let mut backtrace = None;
 
 		'outer: for (idx, field) in fields.into_iter().enumerate() {
@@ -291,25 +512,16 @@ impl Fields {
 
 					continue 'outer;
 				} else if ident.is_ident("backtrace") {
-					#[cfg(not(feature = "backtrace"))]
-					return Err(syn::Error::new_spanned(
-						attr,
-						"enable feature \"backtrace\" to use #[backtrace]",
-					));
-
-					#[cfg(feature = "backtrace")]
-					{
-						if backtrace.is_some() {
-							return Err(syn::Error::new_spanned(
-								attr,
-								"#[backtrace] is already defined",
-							));
-						}
-
-						let field = Field::from_syn(idx, field);
-						backtrace = Some(field);
-						continue 'outer;
+					if backtrace.is_some() {
+						return Err(syn::Error::new_spanned(
+							attr,
+							"#[backtrace] is already defined",
+						));
 					}
+
+					let field = Field::from_syn(idx, field);
+					backtrace = Some(field);
+					continue 'outer;
 				}
 			}
 
@@ -329,7 +541,6 @@ impl Fields {
 		Ok(Fields {
 			code,
 			location,
-			#[cfg(feature = "backtrace")]
 			backtrace,
 		})
 	}
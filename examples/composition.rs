@@ -10,7 +10,7 @@ struct Error {
 	backtrace: mayerror::Backtrace,
 }
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, thiserror::Error, mayerror::Code)]
 enum ErrorCode {
 	#[error("error reading config")]
 	ConfigError(#[source] ConfigErrorCode),
@@ -36,7 +36,7 @@ struct ConfigError {
 	backtrace: mayerror::Backtrace,
 }
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, thiserror::Error, mayerror::Code)]
 enum ConfigErrorCode {
 	#[error("file not found")]
 	FileNotFound,
@@ -12,7 +12,7 @@ struct Error {
 	backtrace: mayerror::Backtrace,
 }
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, thiserror::Error, mayerror::Code)]
 enum ErrorCode {
 	#[error("source error")]
 	Source(#[from] MayValError),
@@ -24,6 +24,7 @@ enum ErrorCode {
 #[error("may val error")]
 struct MayValError;
 
+#[mayerror::main]
 fn main() -> Result<(), Error> {
 	one()?;
 	Ok(())
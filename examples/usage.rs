@@ -1,4 +1,4 @@
-use mayerror::MayError;
+use mayerror::{MayError, Report};
 use std::{
 	num::ParseIntError,
 	path::{Path, PathBuf},
@@ -14,9 +14,10 @@ struct Error {
 	backtrace: mayerror::Backtrace,
 }
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, thiserror::Error, mayerror::Code)]
 enum ErrorCode {
 	#[error("file {0:?} not found")]
+	#[mayerror(exit_code = 66)]
 	FileNotFound(PathBuf),
 	#[error("io error")]
 	Io(#[from] std::io::Error),
@@ -46,10 +47,14 @@ impl Parser {
 	}
 }
 
-fn main() -> Result<(), Error> {
+fn run() -> Result<(), Error> {
 	let parser = Parser::read("file.txt")?;
 	let content = parser.parse()?;
 	println!("content is {}", content);
 
 	Ok(())
 }
+
+fn main() -> Report<Error> {
+	Report::from(run())
+}
@@ -1,117 +1,358 @@
+use crate::config::{
+	async_mode, capture_policy, color_bt, first_party_crates, frame_filtering_configured,
+	frame_filters, start_at_location, theme, verbosity, verbosity_configured, CapturePolicy,
+	ColorBt, Verbosity,
+};
 use once_cell::sync::Lazy;
 use owo_colors::OwoColorize;
 use std::{
+	borrow::Cow,
+	collections::{HashMap, HashSet},
 	fmt::Display,
-	fs::File,
-	io::{BufRead, BufReader},
-	path::PathBuf,
+	panic::Location,
+	path::{Component, Path, PathBuf},
+	sync::{Arc, Mutex},
 };
 
-#[doc(hidden)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Verbosity {
-	Minimal,
-	Medium,
-	Full,
-}
+mod capture;
+mod highlight;
+mod paths;
+#[cfg(feature = "backtrace")]
+mod raw;
+mod source;
+#[cfg(not(feature = "backtrace"))]
+mod std_backtrace;
 
-impl Verbosity {
-	fn from_env() -> Self {
-		match std::env::var("RUST_LIB_BACKTRACE").or_else(|_| std::env::var("RUST_BACKTRACE")) {
-			Ok(s) if s == "full" => Verbosity::Full,
-			Ok(s) if s != "0" => Verbosity::Medium,
-			_ => Verbosity::Minimal,
+pub use self::capture::Backtrace;
+use self::paths::ShortPaths;
+#[cfg(feature = "backtrace")]
+pub use self::raw::{ParseRawBacktraceError, RawBacktrace};
+pub use self::source::Source;
+use self::source::SourceCache;
+
+/// whether the backtrace of an error created at `location` is captured,
+/// and how many frames of it.
+///
+/// [`CapturePolicy::Sampled`] counts the errors of each call site separately,
+/// so interleaved call sites can't starve each other.
+fn should_capture(policy: CapturePolicy, location: &'static Location<'static>) -> Option<usize> {
+	static CALL_SITES: Lazy<Mutex<HashSet<&'static Location<'static>>>> = Lazy::new(Mutex::default);
+	static SAMPLED: Lazy<Mutex<HashMap<&'static Location<'static>, u64>>> =
+		Lazy::new(Mutex::default);
+
+	match policy {
+		CapturePolicy::Always => Some(usize::MAX),
+		CapturePolicy::Never => None,
+		CapturePolicy::OncePerCallSite => {
+			let mut call_sites = CALL_SITES.lock().unwrap_or_else(|err| err.into_inner());
+			call_sites.insert(location).then_some(usize::MAX)
+		}
+		CapturePolicy::Sampled(n) => {
+			let mut sampled = SAMPLED.lock().unwrap_or_else(|err| err.into_inner());
+			let count = sampled.entry(location).or_insert(0);
+			let capture = *count % u64::from(n.max(1)) == 0;
+			*count += 1;
+			capture.then_some(usize::MAX)
 		}
+		CapturePolicy::Top(k) => Some(k),
 	}
 }
 
+/// captures a backtrace starting at the caller of the function that called `trace`.
+///
+/// this is called by the `From` impl generated by `#[derive(MayError)]`, so the
+/// frames of `trace` itself and of the `From` impl are skipped. they are found by
+/// comparing the address of `trace` with the address of the function of each frame,
+/// so this doesn't need to resolve any symbols.
+///
+/// the `policy` of the error code takes precedence over the global one.
 #[doc(hidden)]
-pub static VERBOSITY: Lazy<Verbosity> = Lazy::new(Verbosity::from_env);
+#[cfg(feature = "backtrace")]
+#[inline(never)]
+pub fn trace(
+	policy: Option<CapturePolicy>,
+	location: &'static Location<'static>,
+) -> self::Backtrace {
+	let this = trace as fn(_, _) -> self::Backtrace as usize;
 
-#[doc(hidden)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ColorBt {
-	Show,
-	Hide,
-}
+	let policy = policy.unwrap_or_else(capture_policy);
+	let Some(limit) = should_capture(policy, location) else {
+		return Backtrace::not_captured();
+	};
 
-impl ColorBt {
-	fn from_env() -> Self {
-		match std::env::var("COLORBT_SHOW_HIDDEN") {
-			Ok(s) if s != "0" => ColorBt::Show,
-			_ => ColorBt::Hide,
+	let mut frames = Vec::new();
+	let mut caller = None;
+	backtrace::trace(|frame| {
+		if caller.is_none() && frame.symbol_address() as usize == this {
+			caller = Some(frames.len() + 1);
 		}
+
+		frames.push(backtrace::BacktraceFrame::from(frame.clone()));
+		caller.map_or(true, |caller| frames.len() - caller <= limit)
+	});
+
+	if let Some(caller) = caller {
+		frames.drain(..=caller.min(frames.len() - 1));
 	}
-}
 
-#[doc(hidden)]
-pub static COLOR_BT: Lazy<ColorBt> = Lazy::new(ColorBt::from_env);
+	Backtrace::from_frames(frames)
+}
 
+/// captures a backtrace starting at the caller of the function that called `trace`.
+///
+/// [`std::backtrace::Backtrace`] doesn't expose the addresses of its frames, so the
+/// frames of `trace` and of the `From` impl are skipped by their names once the
+/// backtrace is resolved.
 #[doc(hidden)]
-pub type Backtrace = backtrace::Backtrace;
+#[cfg(not(feature = "backtrace"))]
+#[inline(never)]
+pub fn trace(
+	policy: Option<CapturePolicy>,
+	location: &'static Location<'static>,
+) -> self::Backtrace {
+	let policy = policy.unwrap_or_else(capture_policy);
+	let Some(limit) = should_capture(policy, location) else {
+		return Backtrace::not_captured();
+	};
 
-#[doc(hidden)]
-pub fn trace() -> self::Backtrace {
-	if *VERBOSITY >= Verbosity::Medium {
-		backtrace::Backtrace::new()
-	} else {
-		backtrace::Backtrace::new_unresolved()
-	}
+	let backtrace = std::backtrace::Backtrace::force_capture();
+	Backtrace::from_std(std_backtrace::Captured::caller(backtrace, limit))
 }
 
-#[derive(Debug)]
-struct Source<'a>(&'a Frame);
+/// what to do with a frame, as decided by a [`Config::frame_filter`].
+///
+/// [`Config::frame_filter`]: crate::Config::frame_filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameAction {
+	/// hide the frame.
+	Hide,
+	/// show the frame as dependency code.
+	Dependency,
+	/// show the frame as your own code, even if it would be hidden otherwise.
+	Keep,
+}
 
-impl Display for Source<'_> {
-	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		let Some((file, lineno)) = self.0.file.as_deref().zip(self.0.line) else {
-			return Ok(());
-		};
+type FilterFn = dyn Fn(&Frame) -> Option<FrameAction> + Send + Sync;
 
-		let file = match File::open(file) {
-			Ok(file) => file,
-			Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
-			e @ Err(_) => e.unwrap(),
-		};
+#[derive(Clone)]
+pub(crate) struct FrameFilter(Arc<FilterFn>);
 
-		// 2 lines at the start, since lines are 1-indexed
-		let start = lineno.saturating_sub(3);
-
-		let reader = BufReader::new(file);
-		let lines = reader
-			.lines()
-			.map_while(Result::ok)
-			.zip(1..)
-			.skip(start as usize)
-			.take(5);
-
-		for (line, curr_lineno) in lines {
-			if curr_lineno == lineno {
-				write!(
-					f,
-					"\n{:>8} {} {}",
-					curr_lineno.bold(),
-					">".bold(),
-					line.bold()
-				)?;
-			} else {
-				write!(f, "\n{:>8} | {}", curr_lineno, line)?;
-			}
-		}
+impl FrameFilter {
+	pub(crate) fn new<F>(filter: F) -> Self
+	where
+		F: Fn(&Frame) -> Option<FrameAction> + Send + Sync + 'static,
+	{
+		FrameFilter(Arc::new(filter))
+	}
+}
 
-		Ok(())
+impl std::fmt::Debug for FrameFilter {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str("FrameFilter")
 	}
 }
 
-#[derive(Debug)]
-struct Frame {
+/// a single frame of a backtrace.
+///
+/// functions that were inlined into their caller have a frame of their own,
+/// which shares the [`index`](Frame::index) of the frame they were inlined into.
+#[derive(Debug, Clone)]
+pub struct Frame {
+	/// the position of the symbol in the backtrace, counting inlined symbols.
 	n: usize,
+	/// the position of the physical frame in the stack.
+	depth: usize,
+	/// whether the symbol was inlined into the symbol after it.
+	inlined: bool,
+	/// whether the symbol belongs to the same physical frame as the frame printed before it.
+	continued: bool,
+	ip: usize,
+	module: Option<Module>,
 	name: Option<String>,
 	line: Option<u32>,
+	column: Option<u32>,
 	file: Option<PathBuf>,
+	action: Option<FrameAction>,
+	first_party: Option<bool>,
+	at_location: bool,
+	in_async_mode: bool,
+}
+
+/// how a frame is shown in a backtrace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+	/// the frame of the `#[location]` of the error.
+	Location,
+	/// your own code.
+	Crate,
+	/// the code of dependencies and of the standard library.
+	Dependency,
+}
+
+impl Frame {
+	/// the position of the frame in the stack, starting at 1.
+	pub fn index(&self) -> usize {
+		self.depth
+	}
+
+	/// the demangled name of the function, without the hash suffix.
+	pub fn name(&self) -> Option<&str> {
+		self.name.as_ref().map(|_| self.name_and_hash().0)
+	}
+
+	/// the hash suffix of the name, like `h930b583c2225abb4`, if it has one.
+	pub fn hash(&self) -> Option<&str> {
+		let (_, hash_suffix) = self.name_and_hash();
+		hash_suffix.strip_prefix("::")
+	}
+
+	/// the source file of the frame.
+	pub fn file(&self) -> Option<&Path> {
+		self.file.as_deref()
+	}
+
+	/// the line number in the source file.
+	pub fn line(&self) -> Option<u32> {
+		self.line
+	}
+
+	/// the column in the source file.
+	pub fn column(&self) -> Option<u32> {
+		self.column
+	}
+
+	/// whether the function was inlined into the function of the frame after it.
+	pub fn is_inlined(&self) -> bool {
+		self.inlined
+	}
+
+	/// how the frame is shown, by the same rules as the rendered backtrace.
+	///
+	/// the frames of [`MayError::frames`] know the crate and the `#[location]` of
+	/// their error, like its rendered backtrace. the frames of [`Backtrace::frames`]
+	/// don't, so they are never [`FrameKind::Location`], and only the crates of
+	/// [`Config::first_party_crates`] are your own code, or a guess if there are none.
+	///
+	/// [`MayError::frames`]: crate::MayError::frames
+	/// [`Config::first_party_crates`]: crate::Config::first_party_crates
+	pub fn kind(&self) -> FrameKind {
+		if self.at_location {
+			FrameKind::Location
+		} else if self.is_dependency_code() {
+			FrameKind::Dependency
+		} else {
+			FrameKind::Crate
+		}
+	}
+}
+
+/// removes the crate disambiguators of v0 symbol names, like the `[1b2c3d]` of
+/// `core[1b2c3d]::panicking::panic_fmt`, so they look like legacy symbol names.
+pub(super) fn strip_disambiguators(name: &str) -> String {
+	let mut stripped = String::with_capacity(name.len());
+	let mut rest = name;
+	while let Some(idx) = rest.find('[') {
+		let (before, after) = rest.split_at(idx);
+		stripped.push_str(before);
+
+		let follows_ident = before
+			.chars()
+			.next_back()
+			.is_some_and(|c| c.is_alphanumeric() || c == '_');
+		let disambiguator = after[1..]
+			.find(']')
+			.filter(|&len| len > 0 && after[1..=len].bytes().all(|b| b.is_ascii_hexdigit()));
+
+		match disambiguator {
+			Some(len) if follows_ident => rest = &after[len + 2..],
+			_ => {
+				stripped.push('[');
+				rest = &after[1..];
+			}
+		}
+	}
+
+	stripped.push_str(rest);
+	stripped
 }
 
 impl Frame {
+	/// the name without the hash suffix, and the hash suffix including the leading `::`.
+	///
+	/// v0 symbol names don't have a hash suffix, so it is empty.
+	fn name_and_hash(&self) -> (&str, &str) {
+		let name = self.name.as_deref().unwrap_or("<unknown>");
+		match name.len().checked_sub(19).map(|x| name.split_at(x)) {
+			Some((name, hash_suffix)) if !name.is_empty() && hash_suffix.starts_with("::h") => {
+				(name, hash_suffix)
+			}
+			_ => (name, ""),
+		}
+	}
+
+	/// whether both frames are the same call in the same function,
+	/// like the frames of a recursive function.
+	fn is_same_call(&self, other: &Frame) -> bool {
+		self.name == other.name && self.file == other.file && self.line == other.line
+	}
+
+	/// the name of the function an async block or `async fn` was created in,
+	/// if this frame looks like the body of a generated future.
+	///
+	/// the body of an `async fn` shows up as a closure inside of it, so this can't
+	/// be distinguished from an ordinary closure and only applies in async mode.
+	fn async_name(&self) -> Option<&str> {
+		let (name, _) = self.name_and_hash();
+		let (name, suffix) = name.rsplit_once("::")?;
+		let is_future = suffix == "{{closure}}"
+			|| suffix.starts_with("{closure#")
+			|| suffix.starts_with("{async_block#")
+			|| suffix.starts_with("{async_fn_body#");
+
+		is_future.then_some(name)
+	}
+
+	/// the frames of async executors and of `Future::poll` impls
+	/// that only forward to another future.
+	fn is_async_plumbing(&self) -> bool {
+		const SYM_PREFIXES: &[&str] = &[
+			"core::future::",
+			"std::future::",
+			"core::pin::Pin<P> as core::future::future::Future>::poll",
+			"tokio::",
+			"futures_core::",
+			"futures_util::",
+			"futures_executor::",
+			"async_std::",
+			"async_executor::",
+			"async_task::",
+			"async_io::",
+			"smol::",
+		];
+
+		let (name, _) = self.name_and_hash();
+		let name = name.trim_start_matches('<');
+		if SYM_PREFIXES.iter().any(|x| name.starts_with(x)) {
+			return true;
+		}
+
+		name.ends_with(" as core::future::future::Future>::poll") && self.is_dependency_code()
+	}
+
+	/// the frames between the `From` impl of an error and the code that created the error,
+	/// like the `?` operator or `.into()`.
+	fn is_conversion_code(&self) -> bool {
+		const SYM_SUFFIXES: &[&str] = &[">>::from_residual", " as core::convert::Into<U>>::into"];
+
+		const SYM_NAMES: &[&str] = &[
+			"core::ops::function::FnOnce::call_once",
+			"core::result::Result<T,E>::map_err",
+		];
+
+		let (name, _) = self.name_and_hash();
+		SYM_SUFFIXES.iter().any(|x| name.ends_with(x)) || SYM_NAMES.contains(&name)
+	}
+
 	fn is_mayerror_code(&self) -> bool {
 		let Some(name) = self.name.as_deref() else {
 			return false;
@@ -179,11 +420,31 @@ impl Frame {
 		false
 	}
 
+	/// whether this is the frame of a `#[location]`.
+	///
+	/// the location file is relative to the workspace, the frame file is usually absolute.
+	fn is_at_location(&self, location: &Location<'_>) -> bool {
+		self.line == Some(location.line())
+			&& self
+				.file
+				.as_deref()
+				.is_some_and(|file| file.ends_with(location.file()))
+	}
+
+	fn is_dependency_code(&self) -> bool {
+		match (self.action, self.first_party) {
+			(Some(FrameAction::Dependency), _) => true,
+			(Some(FrameAction::Keep), _) => false,
+			(_, Some(first_party)) => !first_party,
+			(_, None) => self.is_dependency_code_heuristic(),
+		}
+	}
+
 	/// taken from
 	/// <https://github.com/eyre-rs/eyre/blob/dded7dededca017b23dde6126bd5596eddb2deca/color-eyre/src/config.rs#L284-L328>
 	///
 	/// licensed under MIT or APACHE 2.0
-	fn is_dependency_code(&self) -> bool {
+	fn is_dependency_code_heuristic(&self) -> bool {
 		const SYM_PREFIXES: &[&str] = &[
 			"std::",
 			"core::",
@@ -230,64 +491,543 @@ impl Frame {
 
 impl Display for Frame {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		write!(f, "{:>2}: ", self.n)?;
+		let paths = ShortPaths::new(None);
+		self.write(&SourceCache::default(), paths.as_ref(), f)
+	}
+}
 
-		let name = self.name.as_deref().unwrap_or("<unknown>");
-		let (name, hash_suffix) = match name.len().checked_sub(19).map(|x| name.split_at(x)) {
-			Some((name, hash_suffix)) if !name.is_empty() && hash_suffix.starts_with("::h") => {
-				(name, hash_suffix)
+impl Frame {
+	/// writes the frame, reading its source snippet from the cache of the backtrace,
+	/// and shortening its path if there is a `ShortPaths`.
+	fn write(
+		&self,
+		sources: &SourceCache,
+		paths: Option<&ShortPaths>,
+		f: &mut std::fmt::Formatter<'_>,
+	) -> std::fmt::Result {
+		// the symbols inlined into a physical frame are indented below it
+		let indent = if self.continued {
+			f.write_str("      ")?;
+			"        "
+		} else {
+			write!(f, "{:>2}: ", self.depth)?;
+			"    "
+		};
+
+		let (name, hash_suffix) = self.name_and_hash();
+
+		let theme = theme();
+		if self.inlined {
+			write!(f, "{} ", "inlined".style(theme.inlined_marker))?;
+		}
+		let is_async = self.in_async_mode && !self.is_dependency_code();
+		let name = match self.async_name().filter(|_| is_async) {
+			Some(name) => {
+				write!(f, "{} ", "async".style(theme.async_marker))?;
+				name
 			}
-			_ => (name, "<unknown>"),
+			None => name,
 		};
 
-		if self.is_dependency_code() {
-			write!(f, "{}", name.green())?;
+		if self.at_location {
+			write!(f, "{} ", "→".style(theme.location_frame))?;
+			write!(f, "{}", name.style(theme.location_frame))?;
+		} else if self.is_dependency_code() {
+			write!(f, "{}", name.style(theme.dependency_code))?;
 		} else {
-			write!(f, "{}", name.red())?;
+			write!(f, "{}", name.style(theme.crate_code))?;
 		}
 		writeln!(f, "{}", hash_suffix)?;
 
-		write!(f, "    at ")?;
+		write!(f, "{}at ", indent)?;
 		if let Some(file) = self.file.as_deref() {
-			write!(f, "{}", file.display().purple())?;
+			let file = match paths {
+				Some(paths) => paths.shorten(file),
+				None => Cow::Borrowed(file),
+			};
+			write!(f, "{}", file.display().style(theme.file))?;
 		} else {
-			write!(f, "{}", "<unknown source file>".purple())?;
+			write!(f, "{}", "<unknown source file>".style(theme.file))?;
 		}
 		if let Some(line) = self.line {
-			write!(f, ":{}", line.purple())?;
+			write!(f, ":{}", line.style(theme.line_number))?;
 		} else {
-			write!(f, ":{}", "<unknown line number>".purple())?;
+			write!(f, ":{}", "<unknown line number>".style(theme.line_number))?;
 		}
 
-		if *VERBOSITY >= Verbosity::Full {
-			write!(f, "{}", Source(self))?;
+		if verbosity() >= Verbosity::Full {
+			// the address belongs to the physical frame, not to the symbols inlined into it
+			if !self.inlined {
+				write!(f, "\n    ip {:#x}", self.ip)?;
+				if let Some(module) = &self.module {
+					let offset = self.ip.wrapping_sub(module.base);
+					match module.name() {
+						Some(name) => write!(f, " ({} + {:#x})", name, offset)?,
+						None => write!(f, " (module offset {:#x})", offset)?,
+					}
+				}
+			}
+
+			if let Some((file, line)) = self.file.as_deref().zip(self.line) {
+				Source::new(file, line, self.column).write(sources, f)?;
+			}
 		}
 
 		Ok(())
 	}
 }
 
+/// an executable or shared library loaded into the process.
+#[derive(Debug, Clone)]
+struct Module {
+	/// the path of the module.
+	path: Option<PathBuf>,
+	/// the address the module is loaded at.
+	base: usize,
+	/// the addresses the module is mapped to, if known.
+	range: std::ops::Range<usize>,
+}
+
+impl Module {
+	/// the module of a frame, so its instruction pointer can be turned into an offset
+	/// that tools like `addr2line` understand.
+	fn of(ip: usize, base: Option<usize>, modules: &[Module]) -> Option<Module> {
+		if let Some(module) = modules.iter().find(|module| module.range.contains(&ip)) {
+			return Some(module.clone());
+		}
+
+		let base = base?;
+		Some(Module {
+			path: None,
+			base,
+			range: base..base,
+		})
+	}
+
+	/// the modules of the process, read from `/proc/self/maps`.
+	///
+	/// the base of a module is the start of its mapping at file offset 0.
+	#[cfg(target_os = "linux")]
+	fn loaded() -> Vec<Module> {
+		let Ok(maps) = std::fs::read_to_string("/proc/self/maps") else {
+			return Vec::new();
+		};
+
+		let mut modules: Vec<Module> = Vec::new();
+		for line in maps.lines() {
+			// 55a2e9d5a000-55a2e9d83000 r-xp 00027000 fd:01 1234 /path/to/binary
+			let mut fields = line.split_whitespace();
+			let (Some(range), Some(_perms), Some(offset), Some(_dev), Some(_inode), Some(path)) = (
+				fields.next(),
+				fields.next(),
+				fields.next(),
+				fields.next(),
+				fields.next(),
+				fields.next(),
+			) else {
+				continue;
+			};
+
+			let Some((start, end)) = range.split_once('-') else {
+				continue;
+			};
+			let (Ok(start), Ok(end), Ok(offset)) = (
+				usize::from_str_radix(start, 16),
+				usize::from_str_radix(end, 16),
+				usize::from_str_radix(offset, 16),
+			) else {
+				continue;
+			};
+
+			if !path.starts_with('/') {
+				continue;
+			}
+
+			let path = Some(PathBuf::from(path));
+			match modules.last_mut() {
+				Some(module) if offset != 0 && module.path == path => module.range.end = end,
+				_ => modules.push(Module {
+					path,
+					base: start - offset,
+					range: start..end,
+				}),
+			}
+		}
+
+		modules
+	}
+
+	#[cfg(not(target_os = "linux"))]
+	fn loaded() -> Vec<Module> {
+		Vec::new()
+	}
+
+	/// the file name of the module.
+	fn name(&self) -> Option<std::borrow::Cow<'_, str>> {
+		let name = self.path.as_deref()?.file_name()?;
+		Some(name.to_string_lossy())
+	}
+}
+
+/// the crates whose frames are shown as your own code.
+///
+/// that is the crate of the error, the files in its workspace,
+/// and the crates from [`Config::first_party_crates`](crate::Config::first_party_crates).
+struct FirstParty {
+	krate: Option<Crate>,
+	/// the workspace the error was built in, if it is known.
+	workspace: Option<PathBuf>,
+	crates: Vec<String>,
+}
+
+impl FirstParty {
+	fn new(krate: Option<Crate>, workspace: Option<&Path>) -> Self {
+		FirstParty {
+			krate,
+			workspace: workspace.map(Path::to_owned),
+			crates: first_party_crates(),
+		}
+	}
+
+	fn is_empty(&self) -> bool {
+		self.krate.is_none() && self.workspace.is_none() && self.crates.is_empty()
+	}
+
+	fn contains(&self, frame: &Frame) -> bool {
+		if let Some(name) = frame.name.as_deref() {
+			// trait impls like `<my_crate::Error as core::convert::From<T>>::from`
+			let name = name.trim_start_matches(['<', '&']);
+			let in_crate = |krate: &str| {
+				name.strip_prefix(krate)
+					.is_some_and(|rest| rest.starts_with("::") || rest.starts_with('['))
+			};
+
+			let krate = self.krate.map(|krate| krate.name);
+			if krate
+				.into_iter()
+				.chain(self.crates.iter().map(String::as_str))
+				.any(in_crate)
+			{
+				return true;
+			}
+		}
+
+		let Some(file) = frame.file.as_deref() else {
+			return false;
+		};
+		let Some(relative) = self
+			.workspace
+			.as_deref()
+			.and_then(|workspace| file.strip_prefix(workspace).ok())
+		else {
+			return false;
+		};
+
+		// build outputs and vendored dependencies aren't your own code,
+		// even if they are in the workspace
+		let top = relative.components().next();
+		!matches!(top, Some(Component::Normal(dir)) if dir == "target" || dir == "vendor")
+	}
+}
+
+/// the crate an error was defined in, recorded by `#[derive(MayError)]`.
 #[doc(hidden)]
-pub struct PrettyBacktrace<'a>(pub &'a backtrace::Backtrace);
+#[derive(Debug, Clone, Copy)]
+pub struct Crate {
+	pub name: &'static str,
+}
 
-impl PrettyBacktrace<'_> {
-	fn frames(&self) -> Vec<Frame> {
-		let frames = self
-			.0
-			.frames()
+#[doc(hidden)]
+pub struct PrettyBacktrace {
+	frames: Vec<Frame>,
+	captured: bool,
+	krate: Option<Crate>,
+	location: Option<&'static Location<'static>>,
+	workspace: Option<PathBuf>,
+}
+
+impl PrettyBacktrace {
+	pub fn new(backtrace: &Backtrace) -> Self {
+		let modules = if verbosity() >= Verbosity::Full {
+			Module::loaded()
+		} else {
+			Vec::new()
+		};
+
+		let frames = backtrace
+			.resolved()
 			.iter()
-			.flat_map(|frame| frame.symbols().iter().map(|sym| (frame.ip(), sym)))
 			.zip(1..)
-			.map(|((_ip, sym), n)| Frame {
-				n,
-				name: sym.name().map(|name| name.to_string()),
-				line: sym.lineno(),
-				file: sym.filename().map(ToOwned::to_owned),
+			.flat_map(|(frame, depth)| {
+				// the last symbol is the function of the physical frame,
+				// the symbols before it were inlined into it
+				let last = frame.symbols.len().saturating_sub(1);
+				let ip = frame.ip.unwrap_or_default();
+				let module = frame
+					.ip
+					.and_then(|ip| Module::of(ip, frame.module_base, &modules));
+				frame
+					.symbols
+					.iter()
+					.enumerate()
+					.map(move |(idx, sym)| Frame {
+						n: 0,
+						depth,
+						inlined: idx < last,
+						continued: false,
+						ip,
+						module: module.clone(),
+						name: sym.name.as_deref().map(strip_disambiguators),
+						line: sym.line,
+						column: sym.column,
+						file: sym.file.clone(),
+						action: None,
+						first_party: None,
+						at_location: false,
+						in_async_mode: async_mode(),
+					})
 			})
-			.collect::<Vec<_>>();
+			.collect();
+
+		PrettyBacktrace {
+			captured: backtrace.is_captured(),
+			..PrettyBacktrace::from_frames(frames)
+		}
+	}
+
+	/// a backtrace of frames that aren't numbered yet.
+	fn from_frames(mut frames: Vec<Frame>) -> Self {
+		for (frame, n) in frames.iter_mut().zip(1..) {
+			frame.n = n;
+		}
+
+		PrettyBacktrace {
+			frames,
+			captured: true,
+			krate: None,
+			location: None,
+			workspace: None,
+		}
+	}
+
+	pub fn krate(mut self, krate: Crate) -> Self {
+		self.krate = Some(krate);
+		self
+	}
+
+	pub fn location(mut self, location: Option<&'static Location<'static>>) -> Self {
+		self.location = location;
+		self
+	}
+
+	/// the workspace of a backtrace without a location, like the one of a panic.
+	pub(crate) fn in_workspace(mut self, workspace: Option<PathBuf>) -> Self {
+		self.workspace = workspace;
+		self
+	}
+
+	pub fn into_frames(self) -> Frames {
+		Frames(self.visible_frames().into_iter())
+	}
+}
+
+impl PrettyBacktrace {
+	fn frames(&self) -> Vec<Frame> {
+		let mut frames = self.frames.clone();
+
+		if let Some(location) = self.location {
+			let at_location = frames
+				.iter_mut()
+				.find(|frame| frame.is_at_location(location));
+			if let Some(frame) = at_location {
+				frame.at_location = true;
+			}
+		}
+
+		let workspace = self.workspace();
+		let first_party = FirstParty::new(self.krate, workspace.as_deref());
+		if !first_party.is_empty() {
+			for frame in &mut frames {
+				frame.first_party = Some(first_party.contains(frame));
+			}
+		}
+
+		let filters = frame_filters();
+		if !filters.is_empty() {
+			for frame in &mut frames {
+				frame.action = filters.iter().find_map(|filter| (filter.0)(frame));
+			}
+		}
+
+		if async_mode() {
+			remove_async_plumbing(&mut frames);
+		}
 
 		frames
 	}
+
+	/// the root of the workspace the error was built in.
+	///
+	/// the file of a location in a workspace member is relative to the workspace root,
+	/// while the file of its frame is absolute, so the workspace root is in front of it.
+	/// crates outside of the workspace have absolute location files, and no workspace.
+	pub fn workspace(&self) -> Option<PathBuf> {
+		if let Some(workspace) = &self.workspace {
+			return Some(workspace.clone());
+		}
+
+		self.workspace_at(self.location?)
+	}
+
+	/// the root of the workspace, found by the frame at `location`.
+	pub(crate) fn workspace_at(&self, location: &Location<'_>) -> Option<PathBuf> {
+		let relative = Path::new(location.file());
+		if relative.is_absolute() {
+			return None;
+		}
+
+		let frame = self
+			.frames
+			.iter()
+			.find(|frame| frame.is_at_location(location))?;
+		let file = frame.file.as_deref()?;
+		let workspace = file.ancestors().nth(relative.components().count())?;
+
+		Some(workspace.to_owned())
+	}
+
+	/// the frames that are shown, after frame filtering, async mode and `start_at_location`.
+	fn visible_frames(&self) -> Vec<Frame> {
+		visible_frames(self.frames())
+	}
+}
+
+/// the frames that are shown of all `frames` of a backtrace.
+fn visible_frames(mut frames: Vec<Frame>) -> Vec<Frame> {
+	if color_bt() == ColorBt::Hide {
+		filter_frames(&mut frames);
+	}
+
+	if start_at_location() {
+		if let Some(idx) = frames.iter().position(|frame| frame.at_location) {
+			frames.drain(..idx);
+		}
+	}
+
+	frames
+}
+
+/// removes the frames of async executors and futures in async mode.
+///
+/// unlike filtered frames they aren't counted as hidden frames, so the physical frames
+/// after them are renumbered as if they weren't in the backtrace at all.
+fn remove_async_plumbing(frames: &mut Vec<Frame>) {
+	let mut removed = 0;
+	let mut kept = Vec::with_capacity(frames.len());
+	for group in frames.chunk_by(|a, b| a.depth == b.depth) {
+		let len = kept.len();
+		kept.extend(
+			group
+				.iter()
+				.filter(|frame| {
+					frame.action == Some(FrameAction::Keep) || !frame.is_async_plumbing()
+				})
+				.map(|frame| Frame {
+					depth: frame.depth - removed,
+					..frame.clone()
+				}),
+		);
+
+		if kept.len() == len {
+			removed += 1;
+		}
+	}
+
+	// `filter_frames` finds its cutoffs by the position of the frames
+	for (frame, n) in kept.iter_mut().zip(1..) {
+		frame.n = n;
+	}
+
+	*frames = kept;
+}
+
+impl Backtrace {
+	/// the frames of the backtrace that are shown when it is rendered,
+	/// so you can build your own layout with the same filtering rules.
+	///
+	/// ```
+	/// use mayerror::Backtrace;
+	///
+	/// // a compact single-line trace for logs
+	/// let backtrace = Backtrace::capture();
+	/// let trace = backtrace
+	///     .frames()
+	///     .filter(|frame| !frame.is_inlined())
+	///     .map(|frame| match (frame.name(), frame.line()) {
+	///         (Some(name), Some(line)) => format!("{}:{}", name, line),
+	///         (Some(name), None) => name.to_owned(),
+	///         (None, _) => "<unknown>".to_owned(),
+	///     })
+	///     .collect::<Vec<_>>()
+	///     .join(" <- ");
+	/// println!("{}", trace);
+	/// ```
+	///
+	/// the frames of an error are classified by its crate with [`MayError::frames`].
+	///
+	/// [`MayError::frames`]: crate::MayError::frames
+	pub fn frames(&self) -> Frames {
+		PrettyBacktrace::new(self).into_frames()
+	}
+}
+
+/// an iterator over the visible frames of a [`Backtrace`], innermost frame first.
+///
+/// created by [`Backtrace::frames`].
+#[derive(Debug, Clone)]
+pub struct Frames(std::vec::IntoIter<Frame>);
+
+impl Iterator for Frames {
+	type Item = Frame;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.0.next()
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.0.size_hint()
+	}
+}
+
+impl DoubleEndedIterator for Frames {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		self.0.next_back()
+	}
+}
+
+impl ExactSizeIterator for Frames {}
+
+impl std::iter::FusedIterator for Frames {}
+
+/// the visible frames of a backtrace as plain lines without colors or source snippets.
+pub(crate) fn plain_frames(backtrace: &Backtrace) -> Vec<String> {
+	let mut frames = PrettyBacktrace::new(backtrace).frames();
+	if color_bt() == ColorBt::Hide {
+		filter_frames(&mut frames);
+	}
+
+	frames
+		.iter()
+		.map(|frame| {
+			let (name, _) = frame.name_and_hash();
+			let file = frame.file.as_deref().map(|file| file.display());
+			match (file, frame.line) {
+				(Some(file), Some(line)) => format!("{} at {}:{}", name, file, line),
+				(Some(file), None) => format!("{} at {}", name, file),
+				(None, _) => name.to_owned(),
+			}
+		})
+		.collect()
 }
 
 fn filter_frames(frames: &mut Vec<Frame>) {
@@ -295,7 +1035,15 @@ fn filter_frames(frames: &mut Vec<Frame>) {
 		.iter()
 		.rposition(|frame| frame.is_mayerror_code() || frame.is_post_panic_code())
 		.map(|idx| idx + 2) // frames are 1-indexed
-		.unwrap_or(0);
+		.unwrap_or(1);
+
+	// the backtrace of an error starts right after its `From` impl
+	let conversion_cutoff = frames
+		.iter()
+		.skip(mayerror_cutoff - 1)
+		.take_while(|frame| frame.is_conversion_code())
+		.count();
+	let mayerror_cutoff = mayerror_cutoff + conversion_cutoff;
 
 	let runtime_init_cutoff = frames
 		.iter()
@@ -304,7 +1052,67 @@ fn filter_frames(frames: &mut Vec<Frame>) {
 		.unwrap_or(usize::MAX);
 
 	let range = mayerror_cutoff..runtime_init_cutoff;
-	frames.retain(|frame| range.contains(&frame.n));
+	frames.retain(|frame| match frame.action {
+		Some(FrameAction::Hide) => false,
+		Some(FrameAction::Keep) => true,
+		_ => range.contains(&frame.n),
+	});
+}
+
+/// the longest sequence of frames that is checked for repetitions.
+const MAX_REPEAT_PERIOD: usize = 16;
+
+/// finds a sequence of frames at the start of `frames` that repeats right after itself.
+///
+/// returns the length of the sequence and how often it occurs in a row, preferring
+/// the sequence that covers the most frames.
+fn repetition(frames: &[Frame]) -> Option<(usize, usize)> {
+	let mut best = None;
+	let mut best_covered = 0;
+
+	for period in 1..=MAX_REPEAT_PERIOD.min(frames.len() / 2) {
+		let (sequence, rest) = frames.split_at(period);
+		let count = 1 + rest
+			.chunks_exact(period)
+			.take_while(|chunk| {
+				chunk
+					.iter()
+					.zip(sequence)
+					.all(|(frame, other)| frame.is_same_call(other))
+			})
+			.count();
+
+		// collapsing has to hide at least two frames to be worth a marker
+		let covered = (count - 1) * period;
+		if count > 1 && covered >= 2 && covered > best_covered {
+			best = Some((period, count));
+			best_covered = covered;
+		}
+	}
+
+	best
+}
+
+fn print_repeated(
+	period: usize,
+	times: usize,
+	f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+	let tmp = if period == 1 {
+		format!(
+			"{decor} previous frame repeated {times} time{plural} {decor}",
+			decor = "⋮",
+			plural = if times == 1 { "" } else { "s" }
+		)
+	} else {
+		format!(
+			"{decor} previous {period} frames repeated {times} time{plural} {decor}",
+			decor = "⋮",
+			plural = if times == 1 { "" } else { "s" }
+		)
+	};
+
+	write!(f, "{:^80}", tmp.style(theme().hidden_frames))
 }
 
 fn print_hidden(amt: usize, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -314,42 +1122,79 @@ fn print_hidden(amt: usize, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
 		plural = if amt == 1 { "" } else { "s" }
 	);
 
-	write!(f, "{:^80}", tmp.cyan())
+	write!(f, "{:^80}", tmp.style(theme().hidden_frames))
 }
 
-impl Display for PrettyBacktrace<'_> {
+impl Display for PrettyBacktrace {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		write!(f, "{:━^80}", " BACKTRACE ")?;
 
-		let mut frames = self.frames();
-		let last_frame_n = frames.last().map(|frame| frame.n);
-
-		if *COLOR_BT == ColorBt::Hide {
-			filter_frames(&mut frames);
+		if !self.captured {
+			let message = "backtrace not captured (policy)";
+			return write!(f, "\n{:^80}", message.style(theme().hidden_frames));
 		}
 
+		let frames = self.frames();
+		let last_depth = frames.last().map(|frame| frame.depth);
+		let mut frames = visible_frames(frames);
+
 		if frames.is_empty() {
 			return writeln!(f, "<empty backtrace>");
 		}
 
+		// the physical frame comes last in the stack, but is shown first,
+		// with the symbols inlined into it below it
+		for group in frames.chunk_by_mut(|a, b| a.depth == b.depth) {
+			if let Some(idx) = group.iter().position(|frame| !frame.inlined) {
+				group[..=idx].rotate_right(1);
+			}
+
+			for frame in &mut group[1..] {
+				frame.continued = true;
+			}
+		}
+
+		let workspace = self.workspace();
+		let sources = SourceCache::new(workspace.clone());
+		let paths = ShortPaths::new(workspace.as_deref());
+		// hidden and repeated frames are counted by physical frames, like the frame numbers
 		let mut last_printed = 0;
-		for frame in &frames {
-			let delta = frame.n - last_printed;
+		let mut idx = 0;
+		while let Some(frame) = frames.get(idx) {
+			let delta = frame.depth.saturating_sub(last_printed);
 			if delta > 1 {
 				f.write_str("\n")?;
 				print_hidden(delta - 1, f)?;
 			}
 
-			write!(f, "\n{}", frame)?;
+			if let Some((period, count)) = repetition(&frames[idx..]) {
+				let sequence = &frames[idx..idx + period];
+				for frame in sequence {
+					f.write_str("\n")?;
+					frame.write(&sources, paths.as_ref(), f)?;
+				}
+
+				let physical = sequence.chunk_by(|a, b| a.depth == b.depth).count();
+				f.write_str("\n")?;
+				print_repeated(physical, count - 1, f)?;
 
-			last_printed = frame.n;
+				idx += period * count;
+				last_printed = frames[idx - 1].depth;
+				continue;
+			}
+
+			f.write_str("\n")?;
+			frame.write(&sources, paths.as_ref(), f)?;
+
+			last_printed = frame.depth;
+			idx += 1;
 		}
 
 		let last_filtered = frames.last().unwrap();
-		let last_frame_n = last_frame_n.unwrap();
-		if last_filtered.n < last_frame_n {
+		let last_depth = last_depth.unwrap();
+		if last_filtered.depth < last_depth {
 			f.write_str("\n")?;
-			print_hidden(last_frame_n - last_filtered.n, f)?;
+			print_hidden(last_depth - last_filtered.depth, f)?;
 		}
 
 		Ok(())
@@ -361,34 +1206,418 @@ pub struct BacktraceOmitted;
 
 impl Display for BacktraceOmitted {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		match (*VERBOSITY, *COLOR_BT) {
-			(Verbosity::Full, ColorBt::Show) => {}
-			(Verbosity::Full, ColorBt::Hide) => {
-				f.write_str("\n\n")?;
-				f.write_str(
-				"Run with COLORBT_SHOW_HIDDEN=1 environment variable to disable frame filtering.",
-			)?;
-			}
-			(Verbosity::Medium, ColorBt::Show) => {
-				f.write_str("\n\n")?;
-				f.write_str("Run with RUST_BACKTRACE=full to include source snippets.")?;
-			}
-			(Verbosity::Medium, ColorBt::Hide) => {
-				f.write_str("\n\n")?;
-				f.write_str(
-				"Run with COLORBT_SHOW_HIDDEN=1 environment variable to disable frame filtering.\n",
-			)?;
-				f.write_str("Run with RUST_BACKTRACE=full to include source snippets.")?;
-			}
-			(Verbosity::Minimal, _) => {
-				f.write_str("\n\n")?;
-				f.write_str(
-				"Backtrace omitted. Run with RUST_BACKTRACE=1 environment variable to display it.\n",
-			)?;
-				f.write_str("Run with RUST_BACKTRACE=full to include source snippets.")?;
-			}
+		let hints = omitted_hints(
+			verbosity(),
+			color_bt(),
+			!verbosity_configured(),
+			!frame_filtering_configured(),
+		);
+		if !hints.is_empty() {
+			f.write_str("\n\n")?;
+			f.write_str(&hints.join("\n"))?;
 		}
 
 		Ok(())
 	}
 }
+
+/// what is left out of a backtrace, and how to show it.
+///
+/// the environment variables are only suggested if they take effect,
+/// which they don't if the [`Config`](crate::Config) sets the same option.
+fn omitted_hints(
+	verbosity: Verbosity,
+	color_bt: ColorBt,
+	verbosity_from_env: bool,
+	filtering_from_env: bool,
+) -> Vec<&'static str> {
+	let mut hints = Vec::new();
+	if verbosity == Verbosity::Minimal {
+		hints.push(if verbosity_from_env {
+			"Backtrace omitted. Run with RUST_BACKTRACE=1 environment variable to display it."
+		} else {
+			"Backtrace omitted."
+		});
+	} else if color_bt == ColorBt::Hide && filtering_from_env {
+		hints.push(
+			"Run with COLORBT_SHOW_HIDDEN=1 environment variable to disable frame filtering.",
+		);
+	}
+
+	if verbosity < Verbosity::Full && verbosity_from_env {
+		hints.push("Run with RUST_BACKTRACE=full to include source snippets.");
+	}
+
+	hints
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		filter_frames, omitted_hints, remove_async_plumbing, repetition, should_capture, Crate,
+		FirstParty, Frame, FrameAction,
+	};
+	use crate::{config::ColorBt, CapturePolicy, Verbosity};
+	use std::{panic::Location, path::PathBuf};
+
+	/// frames with the given names, all at line 1 of the same file.
+	fn frames(names: &[&str]) -> Vec<Frame> {
+		names
+			.iter()
+			.zip(1..)
+			.map(|(name, depth)| Frame {
+				n: depth,
+				depth,
+				inlined: false,
+				continued: false,
+				ip: 0,
+				module: None,
+				name: Some(name.to_string()),
+				line: Some(1),
+				column: None,
+				file: Some("src/main.rs".into()),
+				action: None,
+				first_party: None,
+				at_location: false,
+				in_async_mode: false,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn no_repetition() {
+		assert_eq!(repetition(&frames(&["a", "b", "c"])), None);
+		assert_eq!(repetition(&frames(&[])), None);
+	}
+
+	#[test]
+	fn direct_recursion() {
+		assert_eq!(
+			repetition(&frames(&["a", "a", "a", "a", "b"])),
+			Some((1, 4))
+		);
+	}
+
+	#[test]
+	fn indirect_recursion() {
+		let frames = frames(&["a", "b", "a", "b", "a", "b", "c"]);
+		assert_eq!(repetition(&frames), Some((2, 3)));
+	}
+
+	#[test]
+	fn hides_at_least_two_frames() {
+		// a single repeated frame isn't worth a marker
+		assert_eq!(repetition(&frames(&["a", "a", "b"])), None);
+		assert_eq!(repetition(&frames(&["a", "a", "a"])), Some((1, 3)));
+		assert_eq!(repetition(&frames(&["a", "b", "a", "b"])), Some((2, 2)));
+	}
+
+	#[test]
+	fn prefers_the_period_hiding_most_frames() {
+		// `a a` repeated twice hides two frames, `a` repeated four times hides three
+		assert_eq!(repetition(&frames(&["a", "a", "a", "a"])), Some((1, 4)));
+
+		let frames = frames(&["a", "b", "a", "a", "b", "a", "a", "b", "a"]);
+		assert_eq!(repetition(&frames), Some((3, 3)));
+	}
+
+	#[test]
+	fn only_from_the_first_frame() {
+		assert_eq!(repetition(&frames(&["c", "a", "a", "a"])), None);
+	}
+
+	#[test]
+	fn same_name_on_another_line() {
+		let mut frames = frames(&["a", "a", "a"]);
+		frames[1].line = Some(2);
+		assert_eq!(repetition(&frames), None);
+	}
+
+	#[test]
+	fn period_is_limited() {
+		let names = (0..20).map(|n| n.to_string()).collect::<Vec<_>>();
+		let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+		let frames = frames(&[names.as_slice(), names.as_slice()].concat());
+		assert_eq!(repetition(&frames), None);
+	}
+
+	/// the names of the frames that are left after `filter_frames`.
+	fn filtered(names: &[&str]) -> Vec<String> {
+		let mut frames = frames(names);
+		filter_frames(&mut frames);
+		frames.into_iter().filter_map(|frame| frame.name).collect()
+	}
+
+	#[test]
+	fn conversion_code() {
+		let conversions = frames(&[
+			"<core::result::Result<T,F> as core::ops::try_trait::FromResidual<core::result::Result<core::convert::Infallible,E>>>::from_residual",
+			"<T as core::convert::Into<U>>::into",
+			"core::result::Result<T,E>::map_err",
+			"core::ops::function::FnOnce::call_once",
+		]);
+		assert!(conversions.iter().all(Frame::is_conversion_code));
+
+		let others = frames(&["app::read", "<app::Error as core::fmt::Display>::fmt"]);
+		assert!(!others.iter().any(Frame::is_conversion_code));
+	}
+
+	#[test]
+	fn skips_conversion_frames() {
+		let question_mark = filtered(&[
+			"<core::result::Result<T,F> as core::ops::try_trait::FromResidual<core::result::Result<core::convert::Infallible,E>>>::from_residual",
+			"app::read",
+			"app::main",
+		]);
+		assert_eq!(question_mark, ["app::read", "app::main"]);
+
+		let into = filtered(&["<T as core::convert::Into<U>>::into", "app::read"]);
+		assert_eq!(into, ["app::read"]);
+
+		let map_err = filtered(&[
+			"core::ops::function::FnOnce::call_once",
+			"core::result::Result<T,E>::map_err",
+			"app::read",
+		]);
+		assert_eq!(map_err, ["app::read"]);
+	}
+
+	#[test]
+	fn skips_conversion_frames_after_mayerror() {
+		let frames = filtered(&[
+			"mayerror::backtrace::trace",
+			"<T as core::convert::Into<U>>::into",
+			"app::read",
+			"std::rt::lang_start::{{closure}}",
+			"main",
+		]);
+		assert_eq!(frames, ["app::read"]);
+	}
+
+	#[test]
+	fn keeps_conversion_frames_after_own_code() {
+		let frames = filtered(&[
+			"app::read",
+			"core::ops::function::FnOnce::call_once",
+			"app::main",
+		]);
+		assert_eq!(
+			frames,
+			[
+				"app::read",
+				"core::ops::function::FnOnce::call_once",
+				"app::main"
+			]
+		);
+	}
+
+	fn first_party(krate: Option<&'static str>, workspace: Option<&str>) -> FirstParty {
+		FirstParty {
+			krate: krate.map(|name| Crate { name }),
+			workspace: workspace.map(PathBuf::from),
+			crates: vec!["my_framework".to_owned()],
+		}
+	}
+
+	/// a frame of the function `name` in `file`.
+	fn frame(name: &str, file: &str) -> Frame {
+		let mut frame = frames(&[name]).remove(0);
+		frame.file = Some(file.into());
+		frame
+	}
+
+	#[test]
+	fn first_party_by_name() {
+		let first_party = first_party(Some("app"), None);
+		assert!(first_party.contains(&frame("app::main", "/src/main.rs")));
+		assert!(first_party.contains(&frame(
+			"<app::Error as core::fmt::Debug>::fmt",
+			"/src/lib.rs"
+		)));
+		assert!(first_party.contains(&frame("my_framework::run", "/src/lib.rs")));
+		assert!(!first_party.contains(&frame("application::main", "/src/main.rs")));
+		assert!(!first_party.contains(&frame("tokio::spawn", "/src/lib.rs")));
+	}
+
+	#[test]
+	fn first_party_by_workspace() {
+		let first_party = first_party(Some("app"), Some("/home/may/app"));
+		assert!(first_party.contains(&frame("util::parse", "/home/may/app/util/src/lib.rs")));
+		assert!(first_party.contains(&frame("{{closure}}", "/home/may/app/src/main.rs")));
+		assert!(!first_party.contains(&frame("tokio::spawn", "/home/may/tokio/src/lib.rs")));
+	}
+
+	#[test]
+	fn first_party_excludes_target_and_vendor() {
+		let first_party = first_party(None, Some("/home/may/app"));
+		let generated = frame(
+			"parser::parse",
+			"/home/may/app/target/debug/build/out/parser.rs",
+		);
+		assert!(!first_party.contains(&generated));
+		let vendored = frame(
+			"serde::de::Deserialize",
+			"/home/may/app/vendor/serde/src/de.rs",
+		);
+		assert!(!first_party.contains(&vendored));
+	}
+
+	#[test]
+	fn async_name() {
+		let names = [
+			("app::main::{{closure}}", Some("app::main")),
+			(
+				"app::main::{{closure}}::h0123456789abcdef",
+				Some("app::main"),
+			),
+			("app::fetch::{closure#0}", Some("app::fetch")),
+			("app::fetch::{async_block#1}", Some("app::fetch")),
+			("app::fetch::{async_fn_body#0}", Some("app::fetch")),
+			("app::fetch", None),
+			("{{closure}}", None),
+		];
+		for (name, async_name) in names {
+			assert_eq!(frames(&[name])[0].async_name(), async_name, "{}", name);
+		}
+	}
+
+	#[test]
+	fn async_plumbing() {
+		let plumbing = frames(&[
+			"tokio::runtime::park::CachedParkThread::block_on",
+			"<core::pin::Pin<P> as core::future::future::Future>::poll",
+			"<futures_util::future::future::Map<Fut,F> as core::future::future::Future>::poll",
+			"std::future::poll_fn",
+		]);
+		assert!(plumbing.iter().all(Frame::is_async_plumbing));
+
+		let own_code = frames(&["app::main::{{closure}}", "app::fetch"]);
+		assert!(!own_code.iter().any(Frame::is_async_plumbing));
+	}
+
+	#[test]
+	fn async_plumbing_poll_impls() {
+		let mut frames = frames(&["<app::Task as core::future::future::Future>::poll"]);
+		frames[0].first_party = Some(true);
+		assert!(!frames[0].is_async_plumbing());
+
+		frames[0].first_party = Some(false);
+		assert!(frames[0].is_async_plumbing());
+	}
+
+	/// the names and depths of the frames that are left after `remove_async_plumbing`.
+	fn without_async_plumbing(mut frames: Vec<Frame>) -> Vec<(String, usize)> {
+		remove_async_plumbing(&mut frames);
+		for (frame, n) in frames.iter().zip(1..) {
+			assert_eq!(frame.n, n);
+		}
+
+		frames
+			.into_iter()
+			.map(|frame| (frame.name.unwrap(), frame.depth))
+			.collect()
+	}
+
+	#[test]
+	fn removes_async_plumbing() {
+		let frames = frames(&[
+			"app::fetch::{{closure}}",
+			"<core::pin::Pin<P> as core::future::future::Future>::poll",
+			"tokio::runtime::park::CachedParkThread::block_on",
+			"app::main",
+		]);
+
+		// removed frames aren't counted as hidden, so the frames after them are renumbered
+		assert_eq!(
+			without_async_plumbing(frames),
+			[
+				("app::fetch::{{closure}}".to_owned(), 1),
+				("app::main".to_owned(), 2)
+			]
+		);
+	}
+
+	#[test]
+	fn keeps_async_plumbing() {
+		let mut frames = frames(&["tokio::spawn", "tokio::runtime::block_on", "app::main"]);
+		frames[1].action = Some(FrameAction::Keep);
+
+		assert_eq!(
+			without_async_plumbing(frames),
+			[
+				("tokio::runtime::block_on".to_owned(), 1),
+				("app::main".to_owned(), 2)
+			]
+		);
+	}
+
+	#[test]
+	fn removes_inlined_async_plumbing() {
+		// a physical frame is only removed if all of its symbols are
+		let mut frames = frames(&["core::future::poll_fn", "app::fetch", "app::main"]);
+		frames[1].depth = 1;
+		frames[2].depth = 2;
+
+		assert_eq!(
+			without_async_plumbing(frames),
+			[("app::fetch".to_owned(), 1), ("app::main".to_owned(), 2)]
+		);
+	}
+
+	#[test]
+	fn samples_each_call_site() {
+		let a = Location::caller();
+		let b = Location::caller();
+
+		// interleaved call sites each get their own count, instead of one site
+		// getting every second error and the other none
+		let captured = (0..4)
+			.flat_map(|_| [a, b])
+			.map(|location| should_capture(CapturePolicy::Sampled(2), location).is_some())
+			.collect::<Vec<_>>();
+		assert_eq!(
+			captured,
+			[true, true, false, false, true, true, false, false]
+		);
+	}
+
+	#[test]
+	fn hints_from_env() {
+		assert_eq!(
+			omitted_hints(Verbosity::Minimal, ColorBt::Hide, true, true),
+			[
+				"Backtrace omitted. Run with RUST_BACKTRACE=1 environment variable to display it.",
+				"Run with RUST_BACKTRACE=full to include source snippets.",
+			]
+		);
+		assert_eq!(
+			omitted_hints(Verbosity::Medium, ColorBt::Hide, true, true),
+			[
+				"Run with COLORBT_SHOW_HIDDEN=1 environment variable to disable frame filtering.",
+				"Run with RUST_BACKTRACE=full to include source snippets.",
+			]
+		);
+		assert_eq!(
+			omitted_hints(Verbosity::Medium, ColorBt::Show, true, true),
+			["Run with RUST_BACKTRACE=full to include source snippets."]
+		);
+		assert!(omitted_hints(Verbosity::Full, ColorBt::Show, true, true).is_empty());
+	}
+
+	#[test]
+	fn no_env_hints_when_configured() {
+		assert_eq!(
+			omitted_hints(Verbosity::Minimal, ColorBt::Hide, false, false),
+			["Backtrace omitted."]
+		);
+		assert!(omitted_hints(Verbosity::Medium, ColorBt::Hide, false, false).is_empty());
+		assert_eq!(
+			omitted_hints(Verbosity::Medium, ColorBt::Hide, false, true),
+			["Run with COLORBT_SHOW_HIDDEN=1 environment variable to disable frame filtering."]
+		);
+		assert_eq!(
+			omitted_hints(Verbosity::Full, ColorBt::Hide, true, false),
+			Vec::<&str>::new()
+		);
+	}
+}
@@ -0,0 +1,242 @@
+use once_cell::sync::{Lazy, OnceCell};
+#[cfg(feature = "backtrace")]
+use std::{collections::HashMap, sync::Mutex, sync::Weak};
+use std::{path::PathBuf, sync::Arc};
+
+/// a captured backtrace, like the `#[backtrace]` of an error.
+///
+/// capturing a backtrace only records the addresses of its frames. the symbols are
+/// resolved the first time the backtrace is rendered, so errors that are handled
+/// without being printed don't pay for it. resolved symbols are cached for the whole
+/// process, so backtraces through the same code share their symbol lookups.
+///
+/// a backtrace is a cheap handle to a shared allocation: backtraces with the same frames
+/// are interned, so errors created from the same stack share one backtrace, which is
+/// only resolved once. cloning a backtrace only clones the handle.
+///
+/// with the `std-backtrace` feature and without the `backtrace` feature, the backtrace is
+/// captured with [`std::backtrace::Backtrace`] instead of the `backtrace` crate. then
+/// backtraces aren't interned, as their addresses aren't known before they are resolved.
+///
+/// ```
+/// use mayerror::Backtrace;
+///
+/// fn capture() -> Backtrace {
+///     Backtrace::capture()
+/// }
+///
+/// let backtraces = (0..2).map(|_| capture()).collect::<Vec<_>>();
+/// # #[cfg(feature = "backtrace")]
+/// assert!(Backtrace::ptr_eq(&backtraces[0], &backtraces[1]));
+/// ```
+#[derive(Clone)]
+pub struct Backtrace(Arc<Inner>);
+
+struct Inner {
+	#[cfg(feature = "backtrace")]
+	frames: Vec<backtrace::BacktraceFrame>,
+	#[cfg(not(feature = "backtrace"))]
+	frames: super::std_backtrace::Captured,
+	resolved: OnceCell<Vec<PhysicalFrame>>,
+	captured: bool,
+}
+
+/// a frame of the stack with its resolved symbols.
+pub(super) struct PhysicalFrame {
+	pub(super) ip: Option<usize>,
+	pub(super) module_base: Option<usize>,
+	/// inlined functions come first, the function of the frame itself last.
+	pub(super) symbols: Arc<[Symbol]>,
+}
+
+/// a resolved symbol of a frame.
+#[derive(Debug)]
+pub(super) struct Symbol {
+	pub(super) name: Option<String>,
+	pub(super) file: Option<PathBuf>,
+	pub(super) line: Option<u32>,
+	pub(super) column: Option<u32>,
+}
+
+/// the symbols of every instruction pointer that was resolved so far.
+#[cfg(feature = "backtrace")]
+static SYMBOLS: Lazy<Mutex<HashMap<usize, Arc<[Symbol]>>>> = Lazy::new(Mutex::default);
+
+/// the backtraces that are still alive, keyed by the instruction pointers of their frames.
+#[cfg(feature = "backtrace")]
+static INTERNED: Lazy<Mutex<Interned>> = Lazy::new(Mutex::default);
+
+#[cfg(feature = "backtrace")]
+#[derive(Default)]
+struct Interned {
+	backtraces: HashMap<Box<[usize]>, Weak<Inner>>,
+	/// the number of backtraces at which the dropped ones are removed next.
+	prune_at: usize,
+}
+
+#[cfg(feature = "backtrace")]
+impl Interned {
+	fn get_or_insert(&mut self, frames: Vec<backtrace::BacktraceFrame>) -> Arc<Inner> {
+		let ips = frames.iter().map(|frame| frame.ip() as usize).collect();
+		if let Some(inner) = self.backtraces.get(&ips).and_then(Weak::upgrade) {
+			return inner;
+		}
+
+		// removing dropped backtraces once the table doubled in size keeps inserting cheap
+		if self.backtraces.len() >= self.prune_at {
+			self.backtraces.retain(|_, inner| inner.strong_count() > 0);
+			self.prune_at = (self.backtraces.len() * 2).max(64);
+		}
+
+		let inner = Arc::new(Inner {
+			frames,
+			resolved: OnceCell::new(),
+			captured: true,
+		});
+		self.backtraces.insert(ips, Arc::downgrade(&inner));
+
+		inner
+	}
+}
+
+#[cfg(feature = "backtrace")]
+impl Backtrace {
+	/// captures a backtrace of the current thread, without resolving its symbols.
+	#[inline(never)]
+	pub fn capture() -> Self {
+		let mut frames = Vec::new();
+		backtrace::trace(|frame| {
+			frames.push(backtrace::BacktraceFrame::from(frame.clone()));
+			true
+		});
+
+		Backtrace::from_frames(frames)
+	}
+
+	pub(super) fn from_frames(frames: Vec<backtrace::BacktraceFrame>) -> Self {
+		let mut interned = INTERNED.lock().unwrap_or_else(|err| err.into_inner());
+		Backtrace(interned.get_or_insert(frames))
+	}
+
+	/// the unresolved frames.
+	pub(super) fn unresolved(&self) -> &[backtrace::BacktraceFrame] {
+		&self.0.frames
+	}
+
+	fn resolve(&self) -> Vec<PhysicalFrame> {
+		let mut cache = SYMBOLS.lock().unwrap_or_else(|err| err.into_inner());
+		self.0
+			.frames
+			.iter()
+			.map(|frame| {
+				let ip = frame.ip() as usize;
+				let symbols = cache.entry(ip).or_insert_with(|| resolve(frame));
+				PhysicalFrame {
+					ip: Some(ip),
+					module_base: frame.module_base_address().map(|base| base as usize),
+					symbols: Arc::clone(symbols),
+				}
+			})
+			.collect()
+	}
+}
+
+#[cfg(feature = "backtrace")]
+fn resolve(frame: &backtrace::BacktraceFrame) -> Arc<[Symbol]> {
+	let mut symbols = Vec::new();
+	backtrace::resolve(frame.ip(), |symbol| {
+		symbols.push(Symbol {
+			name: symbol.name().map(|name| name.to_string()),
+			file: symbol.filename().map(ToOwned::to_owned),
+			line: symbol.lineno(),
+			column: symbol.colno(),
+		});
+	});
+
+	symbols.into()
+}
+
+#[cfg(not(feature = "backtrace"))]
+impl Backtrace {
+	/// captures a backtrace of the current thread, without resolving its symbols.
+	#[inline(never)]
+	pub fn capture() -> Self {
+		let backtrace = std::backtrace::Backtrace::force_capture();
+		Backtrace::from_std(super::std_backtrace::Captured::new(backtrace))
+	}
+
+	pub(super) fn from_std(frames: super::std_backtrace::Captured) -> Self {
+		Backtrace(Arc::new(Inner {
+			frames,
+			resolved: OnceCell::new(),
+			captured: true,
+		}))
+	}
+
+	/// the [`std::backtrace::Backtrace`] this backtrace was captured with.
+	pub fn as_std(&self) -> &std::backtrace::Backtrace {
+		self.0.frames.backtrace()
+	}
+
+	fn resolve(&self) -> Vec<PhysicalFrame> {
+		self.0.frames.resolve()
+	}
+}
+
+/// uses a backtrace captured by someone else, e.g. a backtrace provided by another error.
+#[cfg(not(feature = "backtrace"))]
+impl From<std::backtrace::Backtrace> for Backtrace {
+	fn from(backtrace: std::backtrace::Backtrace) -> Self {
+		Backtrace::from_std(super::std_backtrace::Captured::new(backtrace))
+	}
+}
+
+impl Backtrace {
+	/// the placeholder for a backtrace that was skipped by the [`CapturePolicy`].
+	///
+	/// [`CapturePolicy`]: crate::CapturePolicy
+	pub(super) fn not_captured() -> Self {
+		static NOT_CAPTURED: Lazy<Backtrace> = Lazy::new(|| {
+			Backtrace(Arc::new(Inner {
+				#[cfg(feature = "backtrace")]
+				frames: Vec::new(),
+				#[cfg(not(feature = "backtrace"))]
+				frames: super::std_backtrace::Captured::new(std::backtrace::Backtrace::disabled()),
+				resolved: OnceCell::new(),
+				captured: false,
+			}))
+		});
+
+		NOT_CAPTURED.clone()
+	}
+
+	/// whether the backtrace was captured, or skipped by the [`CapturePolicy`].
+	///
+	/// [`CapturePolicy`]: crate::CapturePolicy
+	pub fn is_captured(&self) -> bool {
+		self.0.captured
+	}
+
+	/// whether both handles point to the same backtrace.
+	pub fn ptr_eq(this: &Backtrace, other: &Backtrace) -> bool {
+		Arc::ptr_eq(&this.0, &other.0)
+	}
+
+	/// the frames with their symbols, resolving them if this is the first time they are needed.
+	pub(super) fn resolved(&self) -> &[PhysicalFrame] {
+		self.0.resolved.get_or_init(|| self.resolve())
+	}
+}
+
+impl std::fmt::Debug for Backtrace {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		#[cfg(feature = "backtrace")]
+		return f
+			.debug_list()
+			.entries(self.0.frames.iter().map(|frame| frame.ip()))
+			.finish();
+
+		#[cfg(not(feature = "backtrace"))]
+		std::fmt::Debug::fmt(self.as_std(), f)
+	}
+}
@@ -0,0 +1,489 @@
+/// the kinds of tokens that are highlighted in a source snippet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Token {
+	Keyword,
+	String,
+	Comment,
+	Number,
+	/// identifiers, punctuation and whitespace.
+	Plain,
+}
+
+const KEYWORDS: &[&str] = &[
+	"as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+	"false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+	"ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+	"unsafe", "use", "where", "while",
+];
+
+/// what the highlighter is in the middle of at the end of a line.
+#[derive(Debug, Clone, Copy, Default)]
+enum State {
+	#[default]
+	Code,
+	/// a block comment, which can be nested.
+	BlockComment(usize),
+	/// a string literal, with the number of `#` of a raw string.
+	Str(Option<usize>),
+}
+
+/// a small tokenizer for rust, which is only good enough to highlight snippets.
+///
+/// it is fed the lines of a snippet in order, so comments and strings that
+/// span multiple lines are highlighted, as long as they start inside the snippet.
+#[derive(Debug, Default)]
+pub(super) struct Highlighter {
+	state: State,
+}
+
+impl Highlighter {
+	/// splits a line into tokens.
+	pub(super) fn line<'a>(&mut self, line: &'a str) -> Vec<(Token, &'a str)> {
+		let bytes = line.as_bytes();
+		let mut tokens: Vec<(Token, &'a str)> = Vec::new();
+
+		let mut pos = 0;
+		while pos < bytes.len() {
+			let start = pos;
+			let token = match self.state {
+				State::BlockComment(depth) => {
+					pos = self.block_comment(bytes, pos, depth);
+					Token::Comment
+				}
+				State::Str(hashes) => {
+					pos = self.string(bytes, pos, hashes);
+					Token::String
+				}
+				State::Code => self.code(line, &mut pos),
+			};
+
+			match tokens.last_mut() {
+				Some((last, text)) if *last == token => *text = &line[start - text.len()..pos],
+				_ => tokens.push((token, &line[start..pos])),
+			}
+		}
+
+		tokens
+	}
+
+	fn block_comment(&mut self, bytes: &[u8], mut pos: usize, mut depth: usize) -> usize {
+		while pos < bytes.len() {
+			if bytes[pos..].starts_with(b"/*") {
+				depth += 1;
+				pos += 2;
+			} else if bytes[pos..].starts_with(b"*/") {
+				depth -= 1;
+				pos += 2;
+				if depth == 0 {
+					self.state = State::Code;
+					return pos;
+				}
+			} else {
+				pos += 1;
+			}
+		}
+
+		self.state = State::BlockComment(depth);
+		bytes.len()
+	}
+
+	fn string(&mut self, bytes: &[u8], mut pos: usize, hashes: Option<usize>) -> usize {
+		while pos < bytes.len() {
+			match (bytes[pos], hashes) {
+				(b'\\', None) => pos += 2,
+				(b'"', None) => {
+					self.state = State::Code;
+					return pos + 1;
+				}
+				(b'"', Some(hashes)) => {
+					let end = pos + 1 + hashes;
+					if bytes
+						.get(pos + 1..end)
+						.is_some_and(|x| x.iter().all(|&b| b == b'#'))
+					{
+						self.state = State::Code;
+						return end;
+					}
+					pos += 1;
+				}
+				_ => pos += 1,
+			}
+		}
+
+		self.state = State::Str(hashes);
+		bytes.len()
+	}
+
+	fn code(&mut self, line: &str, pos: &mut usize) -> Token {
+		let bytes = line.as_bytes();
+		let rest = &bytes[*pos..];
+		let is_ident = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_' || !b.is_ascii();
+
+		if rest.starts_with(b"//") {
+			*pos = bytes.len();
+			return Token::Comment;
+		}
+
+		if rest.starts_with(b"/*") {
+			self.state = State::BlockComment(1);
+			*pos += 2;
+			return Token::Comment;
+		}
+
+		if let Some((len, hashes)) = string_start(rest) {
+			self.state = State::Str(hashes);
+			*pos += len;
+			return Token::String;
+		}
+
+		if let Some(len) = char_literal(&line[*pos..]) {
+			*pos += len;
+			return Token::String;
+		}
+
+		match rest[0] {
+			b'0'..=b'9' => {
+				let len = rest
+					.iter()
+					.zip(rest.iter().skip(1).map(Some).chain([None]))
+					.take_while(|(b, next)| {
+						is_ident(b) || (**b == b'.' && next.is_some_and(u8::is_ascii_digit))
+					})
+					.count();
+				*pos += len;
+				Token::Number
+			}
+			b if is_ident(&b) => {
+				let len = rest.iter().take_while(|b| is_ident(b)).count();
+				let word = &line[*pos..*pos + len];
+				*pos += len;
+				if KEYWORDS.contains(&word) {
+					Token::Keyword
+				} else {
+					Token::Plain
+				}
+			}
+			b'\'' => {
+				// a lifetime or a label
+				let len = rest[1..].iter().take_while(|b| is_ident(b)).count();
+				*pos += 1 + len;
+				Token::Plain
+			}
+			_ => {
+				*pos += 1;
+				Token::Plain
+			}
+		}
+	}
+}
+
+/// the length of the start of a string literal like `"`, `b"` or `r#"`,
+/// and the number of `#` if it is a raw string.
+fn string_start(rest: &[u8]) -> Option<(usize, Option<usize>)> {
+	let mut len = 0;
+	if matches!(rest.first(), Some(b'b' | b'c')) {
+		len += 1;
+	}
+
+	let raw = rest.get(len) == Some(&b'r');
+	if raw {
+		len += 1;
+	}
+
+	let hashes = rest[len.min(rest.len())..]
+		.iter()
+		.take_while(|&&b| b == b'#')
+		.count();
+	if raw {
+		len += hashes;
+	}
+
+	(rest.get(len) == Some(&b'"')).then(|| (len + 1, raw.then_some(hashes)))
+}
+
+/// the length of a character literal like `'a'`, `b'\n'` or `'\u{1f980}'`.
+fn char_literal(rest: &str) -> Option<usize> {
+	let prefix = usize::from(rest.starts_with("b'"));
+	let literal = rest[prefix..].strip_prefix('\'')?;
+
+	let len = if let Some(escape) = literal.strip_prefix('\\') {
+		1 + escape.get(1..)?.find('\'')? + 1
+	} else {
+		literal.chars().next()?.len_utf8()
+	};
+
+	(literal[len..].starts_with('\'')).then_some(prefix + 1 + len + 1)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Highlighter, Token};
+
+	/// the tokens of every line, highlighted in order.
+	fn highlight<'a>(lines: &[&'a str]) -> Vec<Vec<(Token, &'a str)>> {
+		let mut highlighter = Highlighter::default();
+		lines.iter().map(|line| highlighter.line(line)).collect()
+	}
+
+	fn line(line: &str) -> Vec<(Token, &str)> {
+		Highlighter::default().line(line)
+	}
+
+	#[test]
+	fn keywords_and_identifiers() {
+		assert_eq!(
+			line("let value = self.get();"),
+			vec![
+				(Token::Keyword, "let"),
+				(Token::Plain, " value = "),
+				(Token::Keyword, "self"),
+				(Token::Plain, ".get();"),
+			]
+		);
+
+		// keywords inside of identifiers aren't highlighted
+		assert_eq!(line("letter as_ref"), vec![(Token::Plain, "letter as_ref")]);
+	}
+
+	#[test]
+	fn numbers() {
+		assert_eq!(
+			line("1_000u32 + 0x1f + 2.5"),
+			vec![
+				(Token::Number, "1_000u32"),
+				(Token::Plain, " + "),
+				(Token::Number, "0x1f"),
+				(Token::Plain, " + "),
+				(Token::Number, "2.5"),
+			]
+		);
+
+		// a method call on a number or a range isn't part of the number
+		assert_eq!(
+			line("1.max(2)"),
+			vec![
+				(Token::Number, "1"),
+				(Token::Plain, ".max("),
+				(Token::Number, "2"),
+				(Token::Plain, ")"),
+			]
+		);
+		assert_eq!(
+			line("0..10"),
+			vec![
+				(Token::Number, "0"),
+				(Token::Plain, ".."),
+				(Token::Number, "10"),
+			]
+		);
+	}
+
+	#[test]
+	fn strings() {
+		assert_eq!(
+			line(r#"f("a \"quoted\" word", x)"#),
+			vec![
+				(Token::Plain, "f("),
+				(Token::String, r#""a \"quoted\" word""#),
+				(Token::Plain, ", x)"),
+			]
+		);
+	}
+
+	#[test]
+	fn raw_strings_with_hashes() {
+		assert_eq!(
+			line(r###"let s = r##"a "# quote"##;"###),
+			vec![
+				(Token::Keyword, "let"),
+				(Token::Plain, " s = "),
+				(Token::String, r###"r##"a "# quote"##"###),
+				(Token::Plain, ";"),
+			]
+		);
+
+		// escapes don't apply to raw strings
+		assert_eq!(
+			line(r#"r"a\" + 1"#),
+			vec![
+				(Token::String, r#"r"a\""#),
+				(Token::Plain, " + "),
+				(Token::Number, "1"),
+			]
+		);
+	}
+
+	#[test]
+	fn byte_and_c_strings() {
+		assert_eq!(
+			line(r##"(b"bytes", c"c string", br#"raw"#, cr"raw c")"##),
+			vec![
+				(Token::Plain, "("),
+				(Token::String, r#"b"bytes""#),
+				(Token::Plain, ", "),
+				(Token::String, r#"c"c string""#),
+				(Token::Plain, ", "),
+				(Token::String, r##"br#"raw"#"##),
+				(Token::Plain, ", "),
+				(Token::String, r#"cr"raw c""#),
+				(Token::Plain, ")"),
+			]
+		);
+
+		// identifiers starting with the prefixes aren't strings
+		assert_eq!(line("bar + cr"), vec![(Token::Plain, "bar + cr")]);
+	}
+
+	#[test]
+	fn strings_spanning_lines() {
+		assert_eq!(
+			highlight(&[r#"let s = "first"#, "second", r#"third" + 1;"#]),
+			vec![
+				vec![
+					(Token::Keyword, "let"),
+					(Token::Plain, " s = "),
+					(Token::String, r#""first"#),
+				],
+				vec![(Token::String, "second")],
+				vec![
+					(Token::String, r#"third""#),
+					(Token::Plain, " + "),
+					(Token::Number, "1"),
+					(Token::Plain, ";"),
+				],
+			]
+		);
+
+		// a raw string only ends with its hashes
+		assert_eq!(
+			highlight(&[r##"r#"a"##, r##"b" still"#;"##]),
+			vec![
+				vec![(Token::String, r##"r#"a"##)],
+				vec![(Token::String, r##"b" still"#"##), (Token::Plain, ";")],
+			]
+		);
+
+		// a trailing backslash continues the string
+		assert_eq!(
+			highlight(&[r#""a \"#, r#"b" x"#]),
+			vec![
+				vec![(Token::String, r#""a \"#)],
+				vec![(Token::String, r#"b""#), (Token::Plain, " x")],
+			]
+		);
+	}
+
+	#[test]
+	fn comments() {
+		assert_eq!(
+			line(r#"x // a "comment" fn"#),
+			vec![
+				(Token::Plain, "x "),
+				(Token::Comment, r#"// a "comment" fn"#)
+			]
+		);
+		assert_eq!(
+			line("a /* b */ c"),
+			vec![
+				(Token::Plain, "a "),
+				(Token::Comment, "/* b */"),
+				(Token::Plain, " c"),
+			]
+		);
+
+		// comment markers inside of strings aren't comments
+		assert_eq!(line(r#""// no""#), vec![(Token::String, r#""// no""#)]);
+	}
+
+	#[test]
+	fn nested_block_comments() {
+		assert_eq!(
+			line("/* a /* b */ c */ fn"),
+			vec![
+				(Token::Comment, "/* a /* b */ c */"),
+				(Token::Plain, " "),
+				(Token::Keyword, "fn"),
+			]
+		);
+
+		assert_eq!(
+			highlight(&["/* a /* b", "*/ still */ x"]),
+			vec![
+				vec![(Token::Comment, "/* a /* b")],
+				vec![(Token::Comment, "*/ still */"), (Token::Plain, " x")],
+			]
+		);
+	}
+
+	#[test]
+	fn char_literals() {
+		assert_eq!(
+			line(r"['a', '\n', '\'', '\u{1f980}', b'x', b'\\']"),
+			vec![
+				(Token::Plain, "["),
+				(Token::String, "'a'"),
+				(Token::Plain, ", "),
+				(Token::String, r"'\n'"),
+				(Token::Plain, ", "),
+				(Token::String, r"'\''"),
+				(Token::Plain, ", "),
+				(Token::String, r"'\u{1f980}'"),
+				(Token::Plain, ", "),
+				(Token::String, "b'x'"),
+				(Token::Plain, ", "),
+				(Token::String, r"b'\\'"),
+				(Token::Plain, "]"),
+			]
+		);
+
+		// a quote inside of a char literal doesn't start a string
+		assert_eq!(
+			line(r#"'"' + x"#),
+			vec![(Token::String, r#"'"'"#), (Token::Plain, " + x")]
+		);
+	}
+
+	#[test]
+	fn lifetimes_and_labels() {
+		assert_eq!(
+			line("fn f<'a>(x: &'a str) -> &'static str"),
+			vec![
+				(Token::Keyword, "fn"),
+				(Token::Plain, " f<'a>(x: &'a str) -> &'static str"),
+			]
+		);
+		assert_eq!(
+			line("'outer: loop { break 'outer; }"),
+			vec![
+				(Token::Plain, "'outer: "),
+				(Token::Keyword, "loop"),
+				(Token::Plain, " { "),
+				(Token::Keyword, "break"),
+				(Token::Plain, " 'outer; }"),
+			]
+		);
+	}
+
+	#[test]
+	fn multibyte() {
+		assert_eq!(
+			line("let größe = 'ü'; // ñ"),
+			vec![
+				(Token::Keyword, "let"),
+				(Token::Plain, " größe = "),
+				(Token::String, "'ü'"),
+				(Token::Plain, "; "),
+				(Token::Comment, "// ñ"),
+			]
+		);
+		assert_eq!(
+			line(r#""日本語" as 名前"#),
+			vec![
+				(Token::String, r#""日本語""#),
+				(Token::Plain, " "),
+				(Token::Keyword, "as"),
+				(Token::Plain, " 名前"),
+			]
+		);
+	}
+}
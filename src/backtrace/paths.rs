@@ -0,0 +1,219 @@
+use crate::config::{path_remaps, shorten_paths};
+use std::{
+	borrow::Cow,
+	path::{Path, PathBuf},
+};
+
+/// shortens the paths of frames for display.
+#[derive(Debug)]
+pub(super) struct ShortPaths {
+	workspace: Option<PathBuf>,
+	home: Option<PathBuf>,
+	cargo_home: Option<PathBuf>,
+	remaps: Vec<(PathBuf, PathBuf)>,
+}
+
+impl ShortPaths {
+	/// paths in the `workspace` become relative to it. without a workspace, the
+	/// current directory is used if it has a `Cargo.toml`, as it usually is the workspace
+	/// when using `cargo run`.
+	///
+	/// returns `None` if paths aren't shortened.
+	pub(super) fn new(workspace: Option<&Path>) -> Option<Self> {
+		if !shorten_paths() {
+			return None;
+		}
+
+		let workspace = match workspace {
+			Some(workspace) => Some(workspace.to_owned()),
+			None => std::env::current_dir()
+				.ok()
+				.filter(|dir| dir.join("Cargo.toml").is_file()),
+		};
+		// every absolute path is relative to the root, which would only lose its leading slash
+		let workspace = workspace.filter(|workspace| workspace.parent().is_some());
+		let home = std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" })
+			.filter(|home| !home.is_empty())
+			.map(PathBuf::from);
+		let cargo_home = std::env::var_os("CARGO_HOME")
+			.filter(|cargo_home| !cargo_home.is_empty())
+			.map(PathBuf::from);
+
+		Some(ShortPaths {
+			workspace,
+			home,
+			cargo_home,
+			remaps: path_remaps(),
+		})
+	}
+
+	pub(super) fn shorten<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+		if let Some(workspace) = self.workspace.as_deref() {
+			if let Ok(relative) = path.strip_prefix(workspace) {
+				return Cow::Borrowed(relative);
+			}
+
+			// the workspace is known by the path it was built at, before remapping
+			if let Ok(relative) = unmap_with(path, &self.remaps).strip_prefix(workspace) {
+				return Cow::Owned(relative.to_owned());
+			}
+		}
+
+		if let Some(krate) = registry_crate(path, self.cargo_home.as_deref()) {
+			return Cow::Borrowed(krate);
+		}
+
+		if let Some(relative) = self
+			.home
+			.as_deref()
+			.and_then(|home| path.strip_prefix(home).ok())
+		{
+			return Cow::Owned(Path::new("~").join(relative));
+		}
+
+		Cow::Borrowed(path)
+	}
+}
+
+/// the path of a file in the cargo registry, starting at its crate,
+/// like `tokio-1.38.0/src/lib.rs` for
+/// `~/.cargo/registry/src/index.crates.io-6f17d22bba15001f/tokio-1.38.0/src/lib.rs`.
+///
+/// the registry is found in `$CARGO_HOME` if it is set, and in any `.cargo` directory otherwise.
+fn registry_crate<'a>(path: &'a Path, cargo_home: Option<&Path>) -> Option<&'a Path> {
+	let in_cargo_home = cargo_home.and_then(|cargo_home| {
+		let registry = cargo_home.join("registry").join("src");
+		path.strip_prefix(registry).ok()
+	});
+	let registry = match in_cargo_home {
+		Some(registry) => registry,
+		None => {
+			let registry = Path::new(".cargo").join("registry").join("src");
+			let mut components = path.components();
+			loop {
+				if let Ok(registry) = components.as_path().strip_prefix(&registry) {
+					break registry;
+				}
+
+				components.next()?;
+			}
+		}
+	};
+
+	// the first directory is the index, like `index.crates.io-6f17d22bba15001f`
+	let mut components = registry.components();
+	components.next()?;
+	let krate = components.as_path();
+	(!krate.as_os_str().is_empty()).then_some(krate)
+}
+
+/// where the source of a file is read from, undoing a `--remap-path-prefix`.
+pub(super) fn unmap(path: &Path) -> Cow<'_, Path> {
+	unmap_with(path, &path_remaps())
+}
+
+fn unmap_with<'a>(path: &'a Path, remaps: &[(PathBuf, PathBuf)]) -> Cow<'a, Path> {
+	for (from, to) in remaps {
+		if let Ok(rest) = path.strip_prefix(to) {
+			return Cow::Owned(from.join(rest));
+		}
+	}
+
+	Cow::Borrowed(path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{registry_crate, unmap_with, ShortPaths};
+	use std::path::{Path, PathBuf};
+
+	fn paths(workspace: Option<&str>, remaps: &[(&str, &str)]) -> ShortPaths {
+		ShortPaths {
+			workspace: workspace.map(PathBuf::from),
+			home: Some(PathBuf::from("/home/may")),
+			cargo_home: None,
+			remaps: remaps
+				.iter()
+				.map(|&(from, to)| (PathBuf::from(from), PathBuf::from(to)))
+				.collect(),
+		}
+	}
+
+	#[test]
+	fn registry() {
+		let path = Path::new(
+			"/home/may/.cargo/registry/src/index.crates.io-6f17d22bba15001f/tokio-1.38.0/src/lib.rs",
+		);
+		assert_eq!(
+			registry_crate(path, None),
+			Some(Path::new("tokio-1.38.0/src/lib.rs"))
+		);
+	}
+
+	#[test]
+	fn registry_in_cargo_home() {
+		let cargo_home = Path::new("/opt/cargo");
+		let path = Path::new(
+			"/opt/cargo/registry/src/index.crates.io-6f17d22bba15001f/tokio-1.38.0/src/lib.rs",
+		);
+		assert_eq!(
+			registry_crate(path, Some(cargo_home)),
+			Some(Path::new("tokio-1.38.0/src/lib.rs"))
+		);
+	}
+
+	#[test]
+	fn not_registry() {
+		// a `registry` module of your own crate
+		let path = Path::new("/home/may/app/src/registry/src/index/lib.rs");
+		assert_eq!(registry_crate(path, None), None);
+
+		let path = Path::new("/home/may/.cargo/registry/src/index.crates.io-6f17d22bba15001f");
+		assert_eq!(registry_crate(path, None), None);
+	}
+
+	#[test]
+	fn shorten() {
+		let paths = paths(Some("/home/may/app"), &[]);
+		assert_eq!(
+			paths.shorten(Path::new("/home/may/app/src/main.rs")),
+			Path::new("src/main.rs")
+		);
+		assert_eq!(
+			paths.shorten(Path::new("/home/may/lib/src/lib.rs")),
+			Path::new("~/lib/src/lib.rs")
+		);
+		assert_eq!(
+			paths.shorten(Path::new(
+				"/home/may/.cargo/registry/src/index.crates.io-6f17d22bba15001f/tokio-1.38.0/src/lib.rs"
+			)),
+			Path::new("tokio-1.38.0/src/lib.rs")
+		);
+		assert_eq!(
+			paths.shorten(Path::new("/rustc/library/core/src/result.rs")),
+			Path::new("/rustc/library/core/src/result.rs")
+		);
+	}
+
+	#[test]
+	fn shorten_remapped() {
+		let paths = paths(Some("/home/may/app"), &[("/home/may/app", "/app")]);
+		assert_eq!(
+			paths.shorten(Path::new("/app/src/main.rs")),
+			Path::new("src/main.rs")
+		);
+	}
+
+	#[test]
+	fn unmap() {
+		let remaps = [(PathBuf::from("/home/may/app"), PathBuf::from("/app"))];
+		assert_eq!(
+			unmap_with(Path::new("/app/src/main.rs"), &remaps),
+			Path::new("/home/may/app/src/main.rs")
+		);
+		assert_eq!(
+			unmap_with(Path::new("/application/src/main.rs"), &remaps),
+			Path::new("/application/src/main.rs")
+		);
+	}
+}
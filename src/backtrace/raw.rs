@@ -0,0 +1,388 @@
+use super::{Backtrace, Module};
+use once_cell::sync::Lazy;
+use std::{
+	collections::HashMap,
+	fmt::Display,
+	fs::File,
+	path::{Path, PathBuf},
+	str::FromStr,
+	sync::Mutex,
+};
+
+/// the first line of a serialised [`RawBacktrace`].
+const HEADER: &str = "mayerror raw backtrace v1";
+
+/// a backtrace that only stores instruction pointers and the modules they belong to.
+///
+/// this doesn't need any symbols, so it can be captured in a stripped binary and
+/// symbolicated somewhere else with the unstripped binary or its separate debug info,
+/// e.g. with the `mayerror symbolicate` tool of the `symbolicate` feature.
+///
+/// it is serialised with [`Display`] and read back with [`FromStr`]:
+///
+/// ```
+/// use mayerror::RawBacktrace;
+///
+/// let raw = RawBacktrace::capture();
+/// let dump = raw.to_string();
+/// assert_eq!(dump.parse::<RawBacktrace>().unwrap(), raw);
+/// ```
+///
+/// the modules are only known on linux. on other platforms, the module
+/// path and build id of a frame are missing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawBacktrace {
+	modules: Vec<RawModule>,
+	frames: Vec<RawFrame>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RawModule {
+	path: Option<PathBuf>,
+	base: usize,
+	build_id: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RawFrame {
+	ip: usize,
+	/// the index of the module in [`RawBacktrace::modules`].
+	module: Option<usize>,
+}
+
+impl RawBacktrace {
+	/// captures a raw backtrace of the current thread without resolving any symbols.
+	pub fn capture() -> Self {
+		RawBacktrace::new(&Backtrace::capture())
+	}
+
+	/// the raw frames of a backtrace, e.g. the `#[backtrace]` of an error.
+	pub fn new(backtrace: &Backtrace) -> Self {
+		let modules = Module::loaded();
+
+		let mut raw = RawBacktrace::default();
+		for frame in backtrace.unresolved() {
+			let ip = frame.ip() as usize;
+			let base = frame.module_base_address().map(|base| base as usize);
+			let module = Module::of(ip, base, &modules).map(|module| raw.module(module));
+			raw.frames.push(RawFrame { ip, module });
+		}
+
+		raw
+	}
+
+	/// the index of a module, adding it if it isn't known yet.
+	fn module(&mut self, module: Module) -> usize {
+		if let Some(idx) = self.modules.iter().position(|raw| raw.base == module.base) {
+			return idx;
+		}
+
+		let build_id = module.path.as_deref().and_then(build_id);
+		self.modules.push(RawModule {
+			path: module.path,
+			base: module.base,
+			build_id,
+		});
+
+		self.modules.len() - 1
+	}
+}
+
+/// the build id of an object file, which is cached since reading it for every
+/// backtrace would be slow.
+fn build_id(path: &Path) -> Option<Vec<u8>> {
+	static BUILD_IDS: Lazy<Mutex<HashMap<PathBuf, Option<Vec<u8>>>>> = Lazy::new(Mutex::default);
+
+	let mut build_ids = BUILD_IDS.lock().unwrap_or_else(|err| err.into_inner());
+	build_ids
+		.entry(path.to_owned())
+		.or_insert_with(|| ObjectInfo::read(path).and_then(|info| info.build_id))
+		.clone()
+}
+
+/// what is needed from an object file to match it to a module.
+struct ObjectInfo {
+	build_id: Option<Vec<u8>>,
+	/// the address of the start of the file, which the module base corresponds to.
+	#[cfg_attr(not(feature = "symbolicate"), allow(dead_code))]
+	image_base: u64,
+}
+
+impl ObjectInfo {
+	fn read(path: &Path) -> Option<ObjectInfo> {
+		use object::{Object, ObjectSegment};
+
+		let file = File::open(path).ok()?;
+		let cache = object::ReadCache::new(file);
+		let object = object::File::parse(&cache).ok()?;
+
+		let build_id = object.build_id().ok().flatten().map(ToOwned::to_owned);
+		let image_base = object
+			.segments()
+			.find(|segment| segment.file_range().0 == 0)
+			.map(|segment| segment.address())
+			.unwrap_or_else(|| object.relative_address_base());
+
+		Some(ObjectInfo {
+			build_id,
+			image_base,
+		})
+	}
+}
+
+impl Display for RawBacktrace {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "{}", HEADER)?;
+
+		for module in &self.modules {
+			write!(f, "module {:#x} ", module.base)?;
+			match &module.build_id {
+				Some(build_id) => build_id.iter().try_for_each(|b| write!(f, "{:02x}", b))?,
+				None => f.write_str("-")?,
+			}
+			match &module.path {
+				Some(path) => writeln!(f, " {}", path.display())?,
+				None => writeln!(f)?,
+			}
+		}
+
+		for frame in &self.frames {
+			match frame.module {
+				Some(module) => writeln!(f, "frame {:#x} {}", frame.ip, module)?,
+				None => writeln!(f, "frame {:#x} -", frame.ip)?,
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// the error returned when parsing a [`RawBacktrace`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseRawBacktraceError {
+	line: usize,
+	reason: &'static str,
+}
+
+impl Display for ParseRawBacktraceError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"invalid raw backtrace on line {}: {}",
+			self.line, self.reason
+		)
+	}
+}
+
+impl std::error::Error for ParseRawBacktraceError {}
+
+impl FromStr for RawBacktrace {
+	type Err = ParseRawBacktraceError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut lines = s.lines().zip(1..);
+		match lines.next() {
+			Some((header, _)) if header.trim() == HEADER => {}
+			_ => {
+				return Err(ParseRawBacktraceError {
+					line: 1,
+					reason: "missing header",
+				})
+			}
+		}
+
+		let mut raw = RawBacktrace::default();
+		for (line, n) in lines {
+			let error = |reason| ParseRawBacktraceError { line: n, reason };
+
+			let line = line.trim_end();
+			if line.is_empty() {
+				continue;
+			}
+
+			let (kind, rest) = line.split_once(' ').ok_or(error("missing fields"))?;
+			match kind {
+				"module" => {
+					// the path is last, as it may contain spaces
+					let mut fields = rest.splitn(3, ' ');
+					let base = fields.next().and_then(hex).ok_or(error("invalid base"))?;
+					let build_id = match fields.next() {
+						Some("-") => None,
+						Some(build_id) => Some(bytes(build_id).ok_or(error("invalid build id"))?),
+						None => return Err(error("missing build id")),
+					};
+					let path = fields.next().filter(|path| !path.is_empty());
+
+					raw.modules.push(RawModule {
+						path: path.map(PathBuf::from),
+						base,
+						build_id,
+					});
+				}
+				"frame" => {
+					let (ip, module) = rest.split_once(' ').ok_or(error("missing module"))?;
+					let ip = hex(ip).ok_or(error("invalid instruction pointer"))?;
+					let module = match module {
+						"-" => None,
+						module => match module.parse() {
+							Ok(module) if module < raw.modules.len() => Some(module),
+							_ => return Err(error("invalid module")),
+						},
+					};
+
+					raw.frames.push(RawFrame { ip, module });
+				}
+				_ => return Err(error("unknown entry")),
+			}
+		}
+
+		Ok(raw)
+	}
+}
+
+fn hex(s: &str) -> Option<usize> {
+	usize::from_str_radix(s.strip_prefix("0x")?, 16).ok()
+}
+
+fn bytes(s: &str) -> Option<Vec<u8>> {
+	if s.len() % 2 == 1 {
+		return None;
+	}
+
+	(0..s.len())
+		.step_by(2)
+		.map(|idx| u8::from_str_radix(s.get(idx..idx + 2)?, 16).ok())
+		.collect()
+}
+
+#[cfg(feature = "symbolicate")]
+mod symbolicate {
+	use super::{ObjectInfo, RawBacktrace, RawModule};
+	use crate::backtrace::{async_mode, strip_disambiguators, Frame, Module, PrettyBacktrace};
+	use std::{fmt::Display, path::Path};
+
+	/// an object file that was matched to a module.
+	struct Object {
+		loader: addr2line::Loader,
+		image_base: u64,
+	}
+
+	impl RawBacktrace {
+		/// resolves the symbols of the backtrace with the given object files,
+		/// and renders it like the backtrace of an error.
+		///
+		/// the object files are either unstripped binaries or their separate debug info.
+		/// they are matched to the modules of the backtrace by their build id, or by their
+		/// file name if either doesn't have one. frames of modules without a matching object
+		/// file are hidden.
+		pub fn symbolicate<P: AsRef<Path>>(&self, objects: &[P]) -> std::io::Result<impl Display> {
+			let infos = objects
+				.iter()
+				.map(|path| (path.as_ref(), ObjectInfo::read(path.as_ref())))
+				.collect::<Vec<_>>();
+
+			let mut loaded = Vec::new();
+			for module in &self.modules {
+				let Some((path, info)) = infos.iter().find(|(path, info)| {
+					let info = info.as_ref();
+					match (
+						&module.build_id,
+						info.and_then(|info| info.build_id.as_ref()),
+					) {
+						(Some(build_id), Some(other)) => build_id == other,
+						_ => module.path.as_deref().and_then(Path::file_name) == path.file_name(),
+					}
+				}) else {
+					loaded.push(None);
+					continue;
+				};
+
+				let loader = addr2line::Loader::new(path).map_err(io_error)?;
+				let image_base = info.as_ref().map_or(0, |info| info.image_base);
+				loaded.push(Some(Object { loader, image_base }));
+			}
+
+			let mut frames = Vec::new();
+			for (raw, depth) in self.frames.iter().zip(1..) {
+				let Some(module_idx) = raw.module else {
+					continue;
+				};
+				let module = &self.modules[module_idx];
+				let Some(object) = &loaded[module_idx] else {
+					continue;
+				};
+
+				let symbols = symbols(raw.ip, module, object)?;
+				let last = symbols.len().saturating_sub(1);
+				frames.extend(symbols.into_iter().enumerate().map(
+					|(idx, (name, file, line, column))| Frame {
+						n: 0,
+						depth,
+						inlined: idx < last,
+						continued: false,
+						ip: raw.ip,
+						module: Some(Module {
+							path: module.path.clone(),
+							base: module.base,
+							range: module.base..module.base,
+						}),
+						name: name.as_deref().map(strip_disambiguators),
+						line,
+						column,
+						file: file.map(Into::into),
+						action: None,
+						first_party: None,
+						at_location: false,
+						in_async_mode: async_mode(),
+					},
+				));
+			}
+
+			Ok(PrettyBacktrace::from_frames(frames))
+		}
+	}
+
+	/// the errors of `addr2line` aren't `Send`, so they can't be wrapped directly.
+	fn io_error(err: impl Display) -> std::io::Error {
+		std::io::Error::other(err.to_string())
+	}
+
+	type Symbol = (Option<String>, Option<String>, Option<u32>, Option<u32>);
+
+	/// the symbols of an instruction pointer, innermost inlined function first.
+	fn symbols(ip: usize, module: &RawModule, object: &Object) -> std::io::Result<Vec<Symbol>> {
+		// the instruction pointer is a return address, so look up the call before it
+		let probe = (ip.wrapping_sub(module.base) as u64)
+			.wrapping_sub(1)
+			.wrapping_add(object.image_base);
+
+		let mut symbols = Vec::new();
+		let mut frames = object.loader.find_frames(probe).map_err(io_error)?;
+		while let Some(frame) = frames.next().map_err(io_error)? {
+			let name = frame
+				.function
+				.as_ref()
+				.and_then(|function| function.raw_name().ok())
+				.map(|name| rustc_demangle::demangle(&name).to_string());
+			let file = frame
+				.location
+				.as_ref()
+				.and_then(|location| location.file)
+				.map(ToOwned::to_owned);
+			let line = frame.location.as_ref().and_then(|location| location.line);
+			let column = frame.location.as_ref().and_then(|location| location.column);
+
+			symbols.push((name, file, line, column));
+		}
+
+		// without debug info, there is still the symbol table
+		if symbols.is_empty() {
+			if let Some(name) = object.loader.find_symbol(probe) {
+				let name = rustc_demangle::demangle(name).to_string();
+				symbols.push((Some(name), None, None, None));
+			}
+		}
+
+		Ok(symbols)
+	}
+}
@@ -0,0 +1,348 @@
+use super::{
+	highlight::{Highlighter, Token},
+	paths::unmap,
+};
+use crate::{
+	config::{snippet_context, theme},
+	Theme,
+};
+use owo_colors::{OwoColorize, Style};
+use std::{
+	borrow::Cow,
+	cell::RefCell,
+	collections::HashMap,
+	fmt::Display,
+	io::Read,
+	panic::Location,
+	path::{Path, PathBuf},
+	rc::Rc,
+};
+
+/// files larger than this are most likely generated, and not worth reading for a snippet.
+const MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// the lines of source code around a line, with a caret under its column if it is known.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct Source<'a> {
+	file: &'a Path,
+	line: u32,
+	column: Option<u32>,
+	workspace: Option<PathBuf>,
+}
+
+impl<'a> Source<'a> {
+	pub(super) fn new(file: &'a Path, line: u32, column: Option<u32>) -> Self {
+		Source {
+			file,
+			line,
+			column,
+			workspace: None,
+		}
+	}
+
+	/// the source of a `#[location]` or of the location of a panic.
+	pub fn location(location: &'a Location<'a>) -> Self {
+		Source::new(
+			Path::new(location.file()),
+			location.line(),
+			Some(location.column()),
+		)
+	}
+
+	/// the workspace a relative file is in, as found by [`PrettyBacktrace::workspace`].
+	///
+	/// [`PrettyBacktrace::workspace`]: super::PrettyBacktrace::workspace
+	pub fn workspace(mut self, workspace: Option<PathBuf>) -> Self {
+		self.workspace = workspace;
+		self
+	}
+
+	/// the path the file is read from.
+	///
+	/// files in a workspace are relative to it, and not to the current directory,
+	/// so without a known workspace the file is also looked for in the directories above
+	/// the executable, which cargo builds in the `target` directory of the workspace.
+	fn path(&self) -> Cow<'a, Path> {
+		if self.file.is_absolute() {
+			return Cow::Borrowed(self.file);
+		}
+
+		if let Some(workspace) = &self.workspace {
+			return Cow::Owned(workspace.join(self.file));
+		}
+
+		if self.file.is_file() {
+			return Cow::Borrowed(self.file);
+		}
+
+		let near_executable = std::env::current_exe().ok().and_then(|exe| {
+			exe.ancestors()
+				.skip(1)
+				.map(|dir| dir.join(self.file))
+				.find(|path| path.is_file())
+		});
+		near_executable.map_or(Cow::Borrowed(self.file), Cow::Owned)
+	}
+}
+
+/// the lines of a file, or the reason why they aren't available.
+type Lines = Result<Rc<[String]>, String>;
+
+/// the files read while rendering a backtrace, so every file is only read once,
+/// even if many frames point into it.
+#[derive(Debug, Default)]
+pub(super) struct SourceCache {
+	files: RefCell<HashMap<PathBuf, Lines>>,
+	/// the workspace the embedded sources are relative to.
+	workspace: Option<PathBuf>,
+}
+
+impl SourceCache {
+	pub(super) fn new(workspace: Option<PathBuf>) -> Self {
+		SourceCache {
+			files: RefCell::default(),
+			workspace,
+		}
+	}
+
+	fn lines(&self, file: &Path) -> Lines {
+		let mut files = self.files.borrow_mut();
+		files
+			.entry(file.to_owned())
+			.or_insert_with(|| read_lines(file, self.workspace.as_deref()))
+			.clone()
+	}
+}
+
+#[cfg_attr(not(feature = "embed-sources"), allow(unused_variables))]
+fn read_lines(file: &Path, workspace: Option<&Path>) -> Lines {
+	let bytes = match read_file(&unmap(file)) {
+		Ok(bytes) => bytes,
+		#[cfg(feature = "embed-sources")]
+		Err(reason) => embedded(file, workspace).ok_or(reason)?,
+		#[cfg(not(feature = "embed-sources"))]
+		Err(reason) => return Err(reason),
+	};
+
+	if bytes.len() as u64 > MAX_FILE_SIZE {
+		return Err("file too large".to_owned());
+	}
+
+	if bytes.contains(&0) {
+		return Err("binary file".to_owned());
+	}
+	let Ok(content) = String::from_utf8(bytes) else {
+		return Err("binary file".to_owned());
+	};
+
+	Ok(content.lines().map(ToOwned::to_owned).collect())
+}
+
+/// the embedded source of a file, which is found by its path relative to the workspace.
+///
+/// the workspace is found in the backtrace, so it has the same path prefix as the file,
+/// even if it was remapped.
+#[cfg(feature = "embed-sources")]
+fn embedded(file: &Path, workspace: Option<&Path>) -> Option<Vec<u8>> {
+	let relative = if file.is_relative() {
+		file
+	} else {
+		file.strip_prefix(workspace?).ok()?
+	};
+
+	let sources = crate::config::embedded_sources()?;
+	sources.get(relative, MAX_FILE_SIZE as usize + 1)
+}
+
+fn read_file(file: &Path) -> Result<Vec<u8>, String> {
+	let reason = |err: std::io::Error| match err.kind() {
+		std::io::ErrorKind::NotFound => "file not found".to_owned(),
+		kind => kind.to_string(),
+	};
+
+	let file = std::fs::File::open(file).map_err(reason)?;
+	let metadata = file.metadata().map_err(reason)?;
+	if !metadata.is_file() {
+		return Err("not a file".to_owned());
+	}
+	if metadata.len() > MAX_FILE_SIZE {
+		return Err("file too large".to_owned());
+	}
+
+	let mut bytes = Vec::new();
+	// the file may have grown since its metadata was read
+	file.take(MAX_FILE_SIZE + 1)
+		.read_to_end(&mut bytes)
+		.map_err(reason)?;
+
+	Ok(bytes)
+}
+
+/// the whitespace in front of the caret, which keeps the tabs of the line
+/// so the caret lines up with the column.
+fn caret_padding(line: &str, column: u32) -> Option<String> {
+	// columns are 1-indexed, and 0 means that the column is unknown
+	let column = usize::try_from(column).ok()?.checked_sub(1)?;
+	let padding = line
+		.chars()
+		.take(column)
+		.map(|c| if c == '\t' { '\t' } else { ' ' })
+		.collect::<String>();
+
+	(padding.chars().count() == column).then_some(padding)
+}
+
+impl Source<'_> {
+	/// writes the snippet, reading the file from the cache.
+	///
+	/// this never fails because of the file, if it can't be read a note is written instead.
+	pub(super) fn write(
+		&self,
+		sources: &SourceCache,
+		f: &mut std::fmt::Formatter<'_>,
+	) -> std::fmt::Result {
+		let theme = theme();
+		let unavailable = |f: &mut std::fmt::Formatter<'_>, reason: &str| {
+			let note = format!("source unavailable ({})", reason);
+			write!(f, "\n{:>8} {}", "", note.style(theme.source_unavailable))
+		};
+
+		let lines = match sources.lines(&self.path()) {
+			Ok(lines) => lines,
+			Err(reason) => return unavailable(f, &reason),
+		};
+
+		// lines are 1-indexed
+		let lineno = self.line as usize;
+		if lineno == 0 || lineno > lines.len() {
+			return unavailable(f, "line out of range");
+		}
+
+		let (before, after) = snippet_context();
+		let start = lineno.saturating_sub(before + 1);
+		let end = lines.len().min(lineno + after);
+
+		// without colors, highlighting would only split the line into more pieces.
+		// like every other color, this only depends on the theme and not on the terminal
+		let mut highlighter = [
+			theme.syntax_keyword,
+			theme.syntax_string,
+			theme.syntax_comment,
+			theme.syntax_number,
+		]
+		.iter()
+		.any(|style| !style.is_plain())
+		.then(Highlighter::default);
+
+		for (line, curr_lineno) in lines[start..end].iter().zip(start + 1..) {
+			if curr_lineno == lineno {
+				write!(
+					f,
+					"\n{:>8} {} ",
+					curr_lineno.style(theme.active_line),
+					">".style(theme.active_line),
+				)?;
+				write_line(f, line, theme.active_line, &theme, highlighter.as_mut())?;
+
+				let padding = self.column.and_then(|column| caret_padding(line, column));
+				if let Some(padding) = padding {
+					write!(
+						f,
+						"\n{:>8}   {}{}",
+						"",
+						padding,
+						"^".style(theme.active_line)
+					)?;
+				}
+			} else {
+				write!(f, "\n{:>8} | ", curr_lineno)?;
+				write_line(f, line, Style::new(), &theme, highlighter.as_mut())?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// writes a line of a snippet, highlighting its tokens if there is a highlighter.
+///
+/// everything that isn't highlighted has the `plain` style.
+fn write_line(
+	f: &mut std::fmt::Formatter<'_>,
+	line: &str,
+	plain: Style,
+	theme: &Theme,
+	highlighter: Option<&mut Highlighter>,
+) -> std::fmt::Result {
+	let Some(highlighter) = highlighter else {
+		return write!(f, "{}", line.style(plain));
+	};
+
+	for (token, text) in highlighter.line(line) {
+		let style = match token {
+			Token::Keyword => theme.syntax_keyword,
+			Token::String => theme.syntax_string,
+			Token::Comment => theme.syntax_comment,
+			Token::Number => theme.syntax_number,
+			Token::Plain => plain,
+		};
+		write!(f, "{}", text.style(style))?;
+	}
+
+	Ok(())
+}
+
+impl Display for Source<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		self.write(&SourceCache::new(self.workspace.clone()), f)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{caret_padding, Source};
+	use std::path::{Path, PathBuf};
+
+	#[test]
+	fn path_in_workspace() {
+		let workspace = Some(PathBuf::from("/home/may/app"));
+		let source = Source::new(Path::new("src/main.rs"), 1, None).workspace(workspace.clone());
+		assert_eq!(source.path(), Path::new("/home/may/app/src/main.rs"));
+
+		let source = Source::new(Path::new("/rustc/src/lib.rs"), 1, None).workspace(workspace);
+		assert_eq!(source.path(), Path::new("/rustc/src/lib.rs"));
+	}
+
+	#[test]
+	fn padding() {
+		assert_eq!(caret_padding("let x = 1;", 1).as_deref(), Some(""));
+		assert_eq!(caret_padding("let x = 1;", 5).as_deref(), Some("    "));
+	}
+
+	#[test]
+	fn padding_keeps_tabs() {
+		assert_eq!(
+			caret_padding("\t\tlet x = 1;", 7).as_deref(),
+			Some("\t\t    ")
+		);
+		assert_eq!(caret_padding("\tx\ty", 4).as_deref(), Some("\t \t"));
+	}
+
+	#[test]
+	fn padding_counts_chars() {
+		assert_eq!(caret_padding("\"ä\" + x", 7).as_deref(), Some("      "));
+	}
+
+	#[test]
+	fn padding_out_of_range() {
+		// 0 means that the column is unknown
+		assert_eq!(caret_padding("let x = 1;", 0), None);
+		assert_eq!(
+			caret_padding("let x = 1;", 11).as_deref(),
+			Some("          ")
+		);
+		assert_eq!(caret_padding("let x = 1;", 12), None);
+		assert_eq!(caret_padding("", 2), None);
+	}
+}
@@ -0,0 +1,347 @@
+use super::capture::{PhysicalFrame, Symbol};
+use std::backtrace::BacktraceStatus;
+
+/// a [`std::backtrace::Backtrace`], and which of its frames are shown.
+pub(super) struct Captured {
+	backtrace: std::backtrace::Backtrace,
+	/// skip the frames up to the caller of `trace`, like the `backtrace` crate backend does.
+	skip_caller: bool,
+	limit: usize,
+}
+
+impl Captured {
+	pub(super) fn new(backtrace: std::backtrace::Backtrace) -> Self {
+		Captured {
+			backtrace,
+			skip_caller: false,
+			limit: usize::MAX,
+		}
+	}
+
+	/// a backtrace captured in `trace`, which starts at the caller of the `From` impl
+	/// and has at most `limit` frames.
+	pub(super) fn caller(backtrace: std::backtrace::Backtrace, limit: usize) -> Self {
+		Captured {
+			backtrace,
+			skip_caller: true,
+			limit,
+		}
+	}
+
+	pub(super) fn backtrace(&self) -> &std::backtrace::Backtrace {
+		&self.backtrace
+	}
+
+	/// the frames of the backtrace, parsed from its alternate [`Display`] output.
+	///
+	/// symbols that share an address with the symbol before them
+	/// were inlined into the same frame.
+	///
+	/// [`Display`]: std::fmt::Display
+	pub(super) fn resolve(&self) -> Vec<PhysicalFrame> {
+		if self.backtrace.status() != BacktraceStatus::Captured {
+			return Vec::new();
+		}
+
+		let mut frames = parse(&format!("{:#}", self.backtrace));
+
+		if self.skip_caller {
+			let trace = frames.iter().position(|(_, symbols)| {
+				symbols
+					.iter()
+					.any(|symbol| symbol.name.as_deref().is_some_and(is_trace))
+			});
+
+			if let Some(trace) = trace {
+				// the frame after `trace` is the `From` impl
+				frames.drain(..(trace + 2).min(frames.len()));
+			}
+		}
+
+		frames.truncate(self.limit);
+		frames
+			.into_iter()
+			.map(|(ip, symbols)| PhysicalFrame {
+				ip: Some(ip),
+				module_base: None,
+				symbols: symbols.into(),
+			})
+			.collect()
+	}
+}
+
+/// whether the symbol is `mayerror::backtrace::trace`.
+fn is_trace(name: &str) -> bool {
+	let name = match name.len().checked_sub(19).map(|x| name.split_at(x)) {
+		Some((name, hash)) if hash.starts_with("::h") => name,
+		_ => name,
+	};
+
+	name.starts_with("mayerror") && name.ends_with("::backtrace::trace")
+}
+
+/// parses lines like
+///
+/// ```text
+///    4:     0x5556d8bfb772 - my_crate::main::h930b583c2225abb4
+///                                at /path/to/my_crate/src/main.rs:5:22
+/// ```
+fn parse(backtrace: &str) -> Vec<(usize, Vec<Symbol>)> {
+	let mut frames: Vec<(usize, Vec<Symbol>)> = Vec::new();
+
+	for line in backtrace.lines() {
+		let line = line.trim_start();
+
+		if let Some(location) = line.strip_prefix("at ") {
+			let Some(symbol) = frames
+				.last_mut()
+				.and_then(|(_, symbols)| symbols.last_mut())
+			else {
+				continue;
+			};
+
+			let (file, lineno, column) = parse_location(location);
+			symbol.file = Some(file.into());
+			symbol.line = lineno;
+			symbol.column = column;
+
+			continue;
+		}
+
+		let Some((idx, rest)) = line.split_once(": ") else {
+			continue;
+		};
+		if idx.is_empty() || !idx.bytes().all(|b| b.is_ascii_digit()) {
+			continue;
+		}
+
+		let Some((ip, name)) = rest.trim_start().split_once(" - ") else {
+			continue;
+		};
+		let Some(ip) = ip
+			.strip_prefix("0x")
+			.and_then(|ip| usize::from_str_radix(ip, 16).ok())
+		else {
+			continue;
+		};
+
+		let symbol = Symbol {
+			name: (name != "<unknown>").then(|| name.to_owned()),
+			file: None,
+			line: None,
+			column: None,
+		};
+
+		// the frames of direct recursion share their address as well, but repeat the same symbol
+		match frames.last_mut() {
+			Some((last_ip, symbols))
+				if *last_ip == ip
+					&& symbols.last().is_some_and(|last| last.name != symbol.name) =>
+			{
+				symbols.push(symbol);
+			}
+			_ => frames.push((ip, vec![symbol])),
+		}
+	}
+
+	frames
+}
+
+/// splits `file:line:column` or `file:line`, where the column is unknown,
+/// into its parts. the file may contain colons itself.
+fn parse_location(location: &str) -> (&str, Option<u32>, Option<u32>) {
+	fn split_number(s: &str) -> Option<(&str, u32)> {
+		let (rest, number) = s.rsplit_once(':')?;
+		Some((rest, number.parse().ok()?))
+	}
+
+	let Some((rest, last)) = split_number(location) else {
+		return (location, None, None);
+	};
+
+	match split_number(rest) {
+		Some((file, lineno)) => (file, Some(lineno), Some(last)),
+		None => (rest, Some(last), None),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse, parse_location};
+
+	/// the ip and the name, file, line and column of the symbols of every frame.
+	type Parsed<'a> = Vec<(
+		usize,
+		Vec<(Option<&'a str>, Option<&'a str>, Option<u32>, Option<u32>)>,
+	)>;
+
+	fn symbols(frames: &[(usize, Vec<super::Symbol>)]) -> Parsed<'_> {
+		frames
+			.iter()
+			.map(|(ip, symbols)| {
+				let symbols = symbols
+					.iter()
+					.map(|symbol| {
+						let file = symbol.file.as_deref().and_then(|file| file.to_str());
+						(symbol.name.as_deref(), file, symbol.line, symbol.column)
+					})
+					.collect();
+				(*ip, symbols)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn frames() {
+		let backtrace = "   0:     0x5568843bfad7 - main::main::h8da7fa642d137fc6
+                               at /tmp/bt/main.rs:5:28
+   1:     0x5568843bf893 - core::ops::function::FnOnce::call_once::h1c9976113389c974
+                               at /rustc/59807616e1fa2540724bfbac14d7976d7e4a3860/library/core/src/ops/function.rs:250:5
+";
+
+		assert_eq!(
+			symbols(&parse(backtrace)),
+			vec![
+				(
+					0x5568843bfad7,
+					vec![(
+						Some("main::main::h8da7fa642d137fc6"),
+						Some("/tmp/bt/main.rs"),
+						Some(5),
+						Some(28)
+					)]
+				),
+				(
+					0x5568843bf893,
+					vec![(
+						Some("core::ops::function::FnOnce::call_once::h1c9976113389c974"),
+						Some("/rustc/59807616e1fa2540724bfbac14d7976d7e4a3860/library/core/src/ops/function.rs"),
+						Some(250),
+						Some(5)
+					)]
+				),
+			]
+		);
+	}
+
+	#[test]
+	fn inlined_symbols_share_an_ip() {
+		let backtrace = "   4:     0x5568843bfa61 - main::inl::h1fbb9c7799dc9068
+                               at /tmp/bt/main.rs:2:38
+   5:     0x5568843bfa61 - main::rec::h3eab4fb1372b532b
+                               at /tmp/bt/main.rs:4:40
+   6:     0x5568843bfa3a - main::rec::h3eab4fb1372b532b
+                               at /tmp/bt/main.rs:4:63
+";
+
+		let frames = parse(backtrace);
+		assert_eq!(frames.len(), 2);
+		assert_eq!(
+			symbols(&frames[..1]),
+			vec![(
+				0x5568843bfa61,
+				vec![
+					(
+						Some("main::inl::h1fbb9c7799dc9068"),
+						Some("/tmp/bt/main.rs"),
+						Some(2),
+						Some(38)
+					),
+					(
+						Some("main::rec::h3eab4fb1372b532b"),
+						Some("/tmp/bt/main.rs"),
+						Some(4),
+						Some(40)
+					),
+				]
+			)]
+		);
+	}
+
+	#[test]
+	fn direct_recursion() {
+		// recursive calls return to the same address, but aren't inlined into each other
+		let backtrace = "   6:     0x5568843bfa3a - main::rec::h3eab4fb1372b532b
+                               at /tmp/bt/main.rs:4:63
+   7:     0x5568843bfa3a - main::rec::h3eab4fb1372b532b
+                               at /tmp/bt/main.rs:4:63
+   8:     0x5568843bfad7 - main::main::h8da7fa642d137fc6
+                               at /tmp/bt/main.rs:5:28
+";
+
+		let frames = parse(backtrace);
+		let lengths = frames
+			.iter()
+			.map(|(ip, symbols)| (*ip, symbols.len()))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			lengths,
+			vec![
+				(0x5568843bfa3a, 1),
+				(0x5568843bfa3a, 1),
+				(0x5568843bfad7, 1)
+			]
+		);
+	}
+
+	#[test]
+	fn no_column() {
+		let backtrace = "   3:     0x55d4e3a1b2c3 - my_crate::run::h0123456789abcdef
+                               at /home/me/my_crate/src/lib.rs:17
+";
+
+		assert_eq!(
+			symbols(&parse(backtrace)),
+			vec![(
+				0x55d4e3a1b2c3,
+				vec![(
+					Some("my_crate::run::h0123456789abcdef"),
+					Some("/home/me/my_crate/src/lib.rs"),
+					Some(17),
+					None
+				)]
+			)]
+		);
+	}
+
+	#[test]
+	fn unknown() {
+		let backtrace = "  12:     0x7f3a2b1c0d0e - <unknown>
+  13:     0x7f3a2b1c0e0f - __libc_start_main
+  14:     0x55d4e3a1b000 - _start
+";
+
+		assert_eq!(
+			symbols(&parse(backtrace)),
+			vec![
+				(0x7f3a2b1c0d0e, vec![(None, None, None, None)]),
+				(
+					0x7f3a2b1c0e0f,
+					vec![(Some("__libc_start_main"), None, None, None)]
+				),
+				(0x55d4e3a1b000, vec![(Some("_start"), None, None, None)]),
+			]
+		);
+	}
+
+	#[test]
+	fn locations() {
+		assert_eq!(
+			parse_location("/tmp/main.rs:5:28"),
+			("/tmp/main.rs", Some(5), Some(28))
+		);
+		assert_eq!(
+			parse_location("/tmp/main.rs:5"),
+			("/tmp/main.rs", Some(5), None)
+		);
+		assert_eq!(
+			parse_location(r"C:\project\src\main.rs:5:28"),
+			(r"C:\project\src\main.rs", Some(5), Some(28))
+		);
+		assert_eq!(
+			parse_location(r"C:\project\src\main.rs:5"),
+			(r"C:\project\src\main.rs", Some(5), None)
+		);
+		assert_eq!(parse_location("/tmp/main.rs"), ("/tmp/main.rs", None, None));
+	}
+}
@@ -0,0 +1,46 @@
+use mayerror::RawBacktrace;
+use std::{io::Read, path::PathBuf, process::ExitCode};
+
+const USAGE: &str = "usage: mayerror symbolicate <dump|-> <object>...
+
+symbolicates a raw backtrace dump with the unstripped binaries or their separate
+debug info, and prints it like the backtrace of an error.";
+
+fn symbolicate(dump: &str, objects: &[PathBuf]) -> Result<String, Box<dyn std::error::Error>> {
+	let dump = if dump == "-" {
+		let mut dump = String::new();
+		std::io::stdin().read_to_string(&mut dump)?;
+		dump
+	} else {
+		std::fs::read_to_string(dump)?
+	};
+
+	let raw = dump.parse::<RawBacktrace>()?;
+	let backtrace = raw.symbolicate(objects)?;
+	Ok(backtrace.to_string())
+}
+
+fn main() -> ExitCode {
+	let args = std::env::args().skip(1).collect::<Vec<_>>();
+	let [command, dump, objects @ ..] = &*args else {
+		eprintln!("{}", USAGE);
+		return ExitCode::from(2);
+	};
+
+	if command != "symbolicate" || objects.is_empty() {
+		eprintln!("{}", USAGE);
+		return ExitCode::from(2);
+	}
+
+	let objects = objects.iter().map(PathBuf::from).collect::<Vec<_>>();
+	match symbolicate(dump, &objects) {
+		Ok(backtrace) => {
+			println!("{}", backtrace);
+			ExitCode::SUCCESS
+		}
+		Err(err) => {
+			eprintln!("mayerror: {}", err);
+			ExitCode::FAILURE
+		}
+	}
+}
@@ -0,0 +1,125 @@
+use std::fmt::Display;
+
+/// a canonical status, mirroring the
+/// [gRPC status codes](https://grpc.github.io/grpc/core/md_doc_statuscodes.html).
+///
+/// ```
+/// use mayerror::{Canonical, MayError};
+///
+/// #[derive(Debug, thiserror::Error, mayerror::Code)]
+/// pub enum ErrorCode {
+///     #[error("user not found")]
+///     #[mayerror(canonical = NotFound)]
+///     UserNotFound,
+///     #[error("database unreachable")]
+///     #[mayerror(canonical = Unavailable)]
+///     Database,
+/// }
+///
+/// #[derive(MayError)]
+/// pub struct Error {
+///     #[code]
+///     code: ErrorCode,
+/// }
+///
+/// let error = Error::from(ErrorCode::UserNotFound);
+/// assert_eq!(error.canonical(), Canonical::NotFound);
+/// assert_eq!(error.canonical() as i32, 5);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum Canonical {
+	/// not an error.
+	Ok = 0,
+	/// the operation was cancelled, typically by the caller.
+	Cancelled = 1,
+	/// unknown error.
+	Unknown = 2,
+	/// the client specified an invalid argument.
+	InvalidArgument = 3,
+	/// the deadline expired before the operation could complete.
+	DeadlineExceeded = 4,
+	/// some requested entity was not found.
+	NotFound = 5,
+	/// the entity that a client attempted to create already exists.
+	AlreadyExists = 6,
+	/// the caller does not have permission to execute the operation.
+	PermissionDenied = 7,
+	/// some resource has been exhausted.
+	ResourceExhausted = 8,
+	/// the system is not in a state required for the operation.
+	FailedPrecondition = 9,
+	/// the operation was aborted.
+	Aborted = 10,
+	/// the operation was attempted past the valid range.
+	OutOfRange = 11,
+	/// the operation is not implemented or not supported.
+	Unimplemented = 12,
+	/// internal error.
+	Internal = 13,
+	/// the service is currently unavailable.
+	Unavailable = 14,
+	/// unrecoverable data loss or corruption.
+	DataLoss = 15,
+	/// the request does not have valid authentication credentials.
+	Unauthenticated = 16,
+}
+
+impl Canonical {
+	/// the name of the status, as used by gRPC, e.g. `NOT_FOUND`.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Canonical::Ok => "OK",
+			Canonical::Cancelled => "CANCELLED",
+			Canonical::Unknown => "UNKNOWN",
+			Canonical::InvalidArgument => "INVALID_ARGUMENT",
+			Canonical::DeadlineExceeded => "DEADLINE_EXCEEDED",
+			Canonical::NotFound => "NOT_FOUND",
+			Canonical::AlreadyExists => "ALREADY_EXISTS",
+			Canonical::PermissionDenied => "PERMISSION_DENIED",
+			Canonical::ResourceExhausted => "RESOURCE_EXHAUSTED",
+			Canonical::FailedPrecondition => "FAILED_PRECONDITION",
+			Canonical::Aborted => "ABORTED",
+			Canonical::OutOfRange => "OUT_OF_RANGE",
+			Canonical::Unimplemented => "UNIMPLEMENTED",
+			Canonical::Internal => "INTERNAL",
+			Canonical::Unavailable => "UNAVAILABLE",
+			Canonical::DataLoss => "DATA_LOSS",
+			Canonical::Unauthenticated => "UNAUTHENTICATED",
+		}
+	}
+}
+
+impl Display for Canonical {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+#[cfg(feature = "tonic")]
+impl From<Canonical> for tonic::Code {
+	fn from(value: Canonical) -> Self {
+		tonic::Code::from_i32(value as i32)
+	}
+}
+
+/// the metadata key the [`Code::id`](crate::Code::id) is stored under
+/// when converting an error into a [`tonic::Status`].
+#[cfg(feature = "tonic")]
+pub const STATUS_METADATA_KEY: &str = "mayerror-code";
+
+#[doc(hidden)]
+#[cfg(feature = "tonic")]
+pub fn tonic_status<E>(error: &E) -> tonic::Status
+where
+	E: crate::MayError,
+	E::Code: Display,
+{
+	let code = error.code();
+	let mut status = tonic::Status::new(error.canonical().into(), code.to_string());
+	if let Ok(id) = error.metadata().id().parse() {
+		status.metadata_mut().insert(STATUS_METADATA_KEY, id);
+	}
+
+	status
+}
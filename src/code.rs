@@ -0,0 +1,356 @@
+use crate::{Canonical, CapturePolicy};
+use std::{marker::PhantomData, panic::Location, process::ExitCode, time::Duration};
+
+/// metadata attached to an error code.
+///
+/// usually implemented via `#[derive(mayerror::Code)]`, which reads the
+/// `#[mayerror(...)]` attributes on the variants of an enum.
+///
+/// ```
+/// #[derive(Debug, thiserror::Error, mayerror::Code)]
+/// pub enum ErrorCode {
+///     #[error("io error")]
+///     Io(#[from] std::io::Error),
+///     #[error("config file empty")]
+///     #[mayerror(exit_code = 78)]
+///     EmptyFile,
+/// }
+///
+/// use mayerror::Code;
+///
+/// assert_eq!(ErrorCode::EmptyFile.exit_code(), Some(78));
+/// ```
+///
+/// all methods have a default, so a code without any metadata can simply be
+/// `impl mayerror::Code for ErrorCode {}`. a `#[code]` that doesn't implement it
+/// at all behaves the same way.
+pub trait Code {
+	/// a stable identifier for this error code.
+	///
+	/// the derive uses the variant name in kebab-case, e.g. `FileNotFound` becomes
+	/// `file-not-found`, which can be overridden with `#[mayerror(id = "...")]`.
+	fn id(&self) -> &'static str {
+		std::any::type_name::<Self>()
+	}
+
+	/// the process exit code for this error code, if any.
+	///
+	/// `0` means success, so the derive rejects it.
+	///
+	/// ```compile_fail
+	/// #[derive(Debug, thiserror::Error, mayerror::Code)]
+	/// pub enum ErrorCode {
+	///     #[error("config file empty")]
+	///     #[mayerror(exit_code = 0)]
+	///     EmptyFile,
+	/// }
+	/// ```
+	fn exit_code(&self) -> Option<u8> {
+		None
+	}
+
+	/// the http status code for this error code, if any.
+	fn status(&self) -> Option<u16> {
+		None
+	}
+
+	/// the canonical status for this error code, if any.
+	fn canonical(&self) -> Option<Canonical> {
+		None
+	}
+
+	/// whether the operation that caused this error code can be retried, if known.
+	fn is_retryable(&self) -> Option<bool> {
+		None
+	}
+
+	/// how long to wait before retrying, if specified.
+	fn retry_after(&self) -> Option<Duration> {
+		None
+	}
+
+	/// when to capture the backtrace of an error with this code,
+	/// overriding the global [`Config::capture`](crate::Config::capture).
+	fn capture(&self) -> Option<CapturePolicy> {
+		None
+	}
+}
+
+/// an error struct created with `#[derive(MayError)]`.
+pub trait MayError: std::error::Error {
+	/// the type of the `#[code]` field.
+	type Code;
+
+	/// the `#[code]` of the error.
+	fn code(&self) -> &Self::Code;
+
+	/// the [`Code`] metadata of the `#[code]`.
+	///
+	/// if the code doesn't implement [`Code`], this has the defaults of all its methods.
+	///
+	/// ```
+	/// use mayerror::MayError;
+	/// use std::process::ExitCode;
+	///
+	/// #[derive(Debug, thiserror::Error)]
+	/// pub enum ErrorCode {
+	///     #[error("config file empty")]
+	///     EmptyFile,
+	/// }
+	///
+	/// #[derive(MayError)]
+	/// pub struct Error {
+	///     #[code]
+	///     code: ErrorCode,
+	/// }
+	///
+	/// let error = Error::from(ErrorCode::EmptyFile);
+	/// assert_eq!(error.metadata().exit_code(), None);
+	/// assert_eq!(error.exit_code(), ExitCode::FAILURE);
+	/// ```
+	fn metadata(&self) -> &dyn Code;
+
+	/// the `#[location]` of the error, if it has one.
+	///
+	/// this is only `Some` if the field is a `&'static Location<'static>`,
+	/// any other type that can be created from one has no location to return.
+	///
+	/// ```
+	/// use mayerror::MayError;
+	/// use std::panic::Location;
+	///
+	/// #[derive(Debug, thiserror::Error)]
+	/// pub enum ErrorCode {
+	///     #[error("config file empty")]
+	///     EmptyFile,
+	/// }
+	///
+	/// pub struct Loc(&'static Location<'static>);
+	///
+	/// impl From<&'static Location<'static>> for Loc {
+	///     fn from(location: &'static Location<'static>) -> Self {
+	///         Loc(location)
+	///     }
+	/// }
+	///
+	/// impl std::fmt::Display for Loc {
+	///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	///         write!(f, "{}:{}", self.0.file(), self.0.line())
+	///     }
+	/// }
+	///
+	/// #[derive(MayError)]
+	/// pub struct Error {
+	///     #[code]
+	///     code: ErrorCode,
+	///     #[location]
+	///     location: Loc,
+	/// }
+	///
+	/// let error = Error::from(ErrorCode::EmptyFile);
+	/// assert!(error.location().is_none());
+	/// ```
+	fn location(&self) -> Option<&'static Location<'static>>;
+
+	/// the `#[backtrace]` of the error, if it has one.
+	///
+	/// without a backtrace feature, this is a placeholder that is never captured.
+	fn backtrace(&self) -> Option<&crate::Backtrace>;
+
+	/// the visible frames of the `#[backtrace]` of the error, if it has one.
+	///
+	/// unlike [`Backtrace::frames`](crate::Backtrace::frames), these are classified
+	/// like the rendered backtrace of the error, by its crate and its `#[location]`.
+	///
+	/// ```
+	/// use mayerror::{FrameKind, MayError};
+	///
+	/// #[derive(Debug, thiserror::Error)]
+	/// pub enum ErrorCode {
+	///     #[error("config file empty")]
+	///     EmptyFile,
+	/// }
+	///
+	/// #[derive(MayError)]
+	/// pub struct Error {
+	///     #[code]
+	///     code: ErrorCode,
+	///     #[location]
+	///     location: &'static std::panic::Location<'static>,
+	///     #[backtrace]
+	///     backtrace: mayerror::Backtrace,
+	/// }
+	///
+	/// let error = Error::from(ErrorCode::EmptyFile);
+	/// for frame in error.frames().unwrap() {
+	///     let marker = match frame.kind() {
+	///         FrameKind::Location => "→",
+	///         FrameKind::Crate => "*",
+	///         FrameKind::Dependency => " ",
+	///     };
+	///     println!("{} {}", marker, frame.name().unwrap_or("<unknown>"));
+	/// }
+	/// ```
+	#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+	fn frames(&self) -> Option<crate::Frames> {
+		self.backtrace().map(crate::Backtrace::frames)
+	}
+
+	/// the process exit code for the error.
+	///
+	/// uses the [`Code::exit_code`] of the code, and falls back
+	/// to [`ExitCode::FAILURE`] if the code doesn't specify one, or specifies `0`.
+	fn exit_code(&self) -> ExitCode {
+		self.metadata()
+			.exit_code()
+			.filter(|&exit_code| exit_code != 0)
+			.map(ExitCode::from)
+			.unwrap_or(ExitCode::FAILURE)
+	}
+
+	/// the http status code for the error.
+	///
+	/// uses the [`Code::status`] of the code, and falls back
+	/// to `500 Internal Server Error` if the code doesn't specify one.
+	fn status(&self) -> u16 {
+		self.metadata().status().unwrap_or(500)
+	}
+
+	/// the canonical status for the error.
+	///
+	/// uses the [`Code::canonical`] of the code, and falls back
+	/// to [`Canonical::Unknown`] if the code doesn't specify one.
+	fn canonical(&self) -> Canonical {
+		self.metadata().canonical().unwrap_or(Canonical::Unknown)
+	}
+
+	/// whether the operation that caused the error can be retried.
+	///
+	/// uses the [`Code::is_retryable`] of the code, and falls back to `false`
+	/// if the code doesn't specify it.
+	///
+	/// ```
+	/// use mayerror::MayError;
+	/// use std::{io::ErrorKind, time::Duration};
+	///
+	/// #[derive(Debug, thiserror::Error, mayerror::Code)]
+	/// pub enum ErrorCode {
+	///     #[error("io error")]
+	///     #[mayerror(retryable = source)]
+	///     Io(#[from] std::io::Error),
+	///     #[error("rate limited")]
+	///     #[mayerror(retry_after = "5s")]
+	///     RateLimited,
+	///     #[error("invalid input")]
+	///     Invalid,
+	/// }
+	///
+	/// #[derive(MayError)]
+	/// pub struct Error {
+	///     #[code]
+	///     code: ErrorCode,
+	/// }
+	///
+	/// assert!(Error::from(std::io::Error::from(ErrorKind::TimedOut)).is_retryable());
+	/// assert!(!Error::from(std::io::Error::from(ErrorKind::NotFound)).is_retryable());
+	/// assert!(!Error::from(ErrorCode::Invalid).is_retryable());
+	///
+	/// let error = Error::from(ErrorCode::RateLimited);
+	/// assert!(error.is_retryable());
+	/// assert_eq!(error.retry_after(), Some(Duration::from_secs(5)));
+	/// ```
+	fn is_retryable(&self) -> bool {
+		self.metadata().is_retryable().unwrap_or(false)
+	}
+
+	/// how long to wait before retrying, if the error is retryable.
+	fn retry_after(&self) -> Option<Duration> {
+		self.metadata().retry_after()
+	}
+}
+
+/// picks the [`Code`] impl of a code if there is one, and the defaults otherwise.
+///
+/// method resolution prefers the impl for `&&CodeOf` and only falls back to
+/// the one for `&CodeOf` if the code doesn't implement [`Code`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __metadata {
+	($code:expr) => {{
+		#[allow(unused_imports)]
+		use $crate::__private::{WithCode as _, WithoutCode as _};
+		(&&$crate::__private::CodeOf($code)).metadata()
+	}};
+}
+
+#[doc(hidden)]
+pub struct CodeOf<'a, T>(pub &'a T);
+
+#[doc(hidden)]
+pub trait WithCode<'a> {
+	fn metadata(self) -> &'a dyn Code;
+}
+
+impl<'a, T: Code> WithCode<'a> for &&CodeOf<'a, T> {
+	fn metadata(self) -> &'a dyn Code {
+		self.0
+	}
+}
+
+#[doc(hidden)]
+pub trait WithoutCode<'a> {
+	fn metadata(self) -> &'a dyn Code;
+}
+
+impl<'a, T> WithoutCode<'a> for &CodeOf<'a, T> {
+	fn metadata(self) -> &'a dyn Code {
+		&Defaults::<T>(PhantomData)
+	}
+}
+
+/// the metadata of a code that doesn't implement [`Code`].
+struct Defaults<T>(PhantomData<T>);
+
+impl<T> Code for Defaults<T> {
+	fn id(&self) -> &'static str {
+		std::any::type_name::<T>()
+	}
+}
+
+/// the location of a `#[location]` field, if it is a `&'static Location<'static>`.
+///
+/// works like [`__metadata!`], with the impl for `&&LocationOf` only applying to locations.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __location {
+	($location:expr) => {{
+		#[allow(unused_imports)]
+		use $crate::__private::{AsLocation as _, NotLocation as _};
+		(&&$crate::__private::LocationOf($location)).location()
+	}};
+}
+
+#[doc(hidden)]
+pub struct LocationOf<'a, T>(pub &'a T);
+
+#[doc(hidden)]
+pub trait AsLocation {
+	fn location(self) -> Option<&'static Location<'static>>;
+}
+
+impl AsLocation for &&LocationOf<'_, &'static Location<'static>> {
+	fn location(self) -> Option<&'static Location<'static>> {
+		Some(self.0)
+	}
+}
+
+#[doc(hidden)]
+pub trait NotLocation {
+	fn location(self) -> Option<&'static Location<'static>>;
+}
+
+impl<T> NotLocation for &LocationOf<'_, T> {
+	fn location(self) -> Option<&'static Location<'static>> {
+		None
+	}
+}
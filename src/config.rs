@@ -0,0 +1,424 @@
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+use crate::backtrace::{Frame, FrameAction, FrameFilter};
+#[cfg(feature = "embed-sources")]
+use crate::EmbeddedSources;
+use crate::Theme;
+use once_cell::sync::Lazy;
+use std::{path::PathBuf, sync::RwLock};
+
+/// how much of a backtrace is shown.
+///
+/// defaults to the value of the `RUST_LIB_BACKTRACE` or `RUST_BACKTRACE` environment variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+	/// don't show a backtrace.
+	Minimal,
+	/// show a backtrace.
+	Medium,
+	/// show a backtrace including source snippets and instruction addresses.
+	Full,
+}
+
+impl Verbosity {
+	fn from_env() -> Self {
+		match std::env::var("RUST_LIB_BACKTRACE").or_else(|_| std::env::var("RUST_BACKTRACE")) {
+			Ok(s) if s == "full" => Verbosity::Full,
+			Ok(s) if s != "0" => Verbosity::Medium,
+			_ => Verbosity::Minimal,
+		}
+	}
+}
+
+static VERBOSITY: Lazy<Verbosity> = Lazy::new(Verbosity::from_env);
+
+/// when the `#[backtrace]` of an error is captured.
+///
+/// set globally via [`Config::capture`], and per error code via [`Code::capture`],
+/// or `#[mayerror(capture = ...)]` with `#[derive(Code)]`, which takes precedence.
+/// errors without a captured backtrace print "backtrace not captured (policy)" instead.
+///
+/// ```
+/// #[derive(Debug, thiserror::Error, mayerror::Code)]
+/// pub enum ErrorCode {
+///     #[error("unexpected token")]
+///     #[mayerror(capture = Never)]
+///     UnexpectedToken,
+///     #[error("unterminated string")]
+///     #[mayerror(capture = Sampled(100))]
+///     Unterminated,
+///     #[error("io error")]
+///     #[mayerror(capture = Top(8))]
+///     Io(#[from] std::io::Error),
+/// }
+/// ```
+///
+/// [`Code::capture`]: crate::Code::capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapturePolicy {
+	/// capture every backtrace.
+	Always,
+	/// never capture a backtrace.
+	Never,
+	/// only capture the backtrace of the first error created at each call site.
+	OncePerCallSite,
+	/// capture the backtrace of one in every `n` errors.
+	Sampled(u32),
+	/// only capture the top `k` frames of the backtrace.
+	Top(usize),
+}
+
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColorBt {
+	Show,
+	Hide,
+}
+
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+impl ColorBt {
+	fn from_env() -> Self {
+		match std::env::var("COLORBT_SHOW_HIDDEN") {
+			Ok(s) if s != "0" => ColorBt::Show,
+			_ => ColorBt::Hide,
+		}
+	}
+}
+
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+static COLOR_BT: Lazy<ColorBt> = Lazy::new(ColorBt::from_env);
+
+/// where the panic hook and [`Report`](crate::Report) print to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+	/// print to stdout.
+	Stdout,
+	/// print to stderr.
+	Stderr,
+}
+
+impl Output {
+	pub(crate) fn write(self, message: &str) {
+		// the print macros are captured by the test harness, writing to the handles isn't
+		match self {
+			Output::Stdout => print!("{}", message),
+			Output::Stderr => eprint!("{}", message),
+		}
+	}
+}
+
+/// the configuration of `mayerror`.
+///
+/// settings that aren't set fall back to their environment variables, or their defaults.
+/// can be installed via [`mayerror::install_with`], and is used by the panic hook
+/// and every `#[derive(MayError)]` error.
+///
+/// ```
+/// use mayerror::{Config, Output, Theme, Verbosity};
+///
+/// let config = Config::new()
+///     .verbosity(Verbosity::Full)
+///     .frame_filtering(false)
+///     .snippet_context(4)
+///     .theme(Theme::new())
+///     .output(Output::Stderr);
+/// mayerror::install_with(config);
+/// ```
+///
+/// [`mayerror::install_with`]: crate::install_with
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+	verbosity: Option<Verbosity>,
+	frame_filtering: Option<bool>,
+	#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+	frame_filters: Vec<FrameFilter>,
+	first_party_crates: Vec<String>,
+	lines_before: Option<usize>,
+	lines_after: Option<usize>,
+	shorten_paths: Option<bool>,
+	path_remaps: Vec<(PathBuf, PathBuf)>,
+	start_at_location: Option<bool>,
+	async_mode: Option<bool>,
+	capture: Option<CapturePolicy>,
+	#[cfg(feature = "embed-sources")]
+	embedded_sources: Option<EmbeddedSources>,
+	theme: Option<Theme>,
+	output: Option<Output>,
+}
+
+impl Config {
+	/// a configuration with nothing set.
+	pub fn new() -> Self {
+		Config::default()
+	}
+
+	/// set the [`Verbosity`] instead of reading it from the environment.
+	pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+		self.verbosity = Some(verbosity);
+		self
+	}
+
+	/// whether to hide frames from the runtime and from `mayerror` itself,
+	/// instead of reading `COLORBT_SHOW_HIDDEN` from the environment.
+	pub fn frame_filtering(mut self, filter: bool) -> Self {
+		self.frame_filtering = Some(filter);
+		self
+	}
+
+	/// add a filter that decides what happens to a backtrace frame.
+	///
+	/// filters are asked in the order they were added, and the first one to return
+	/// [`Some`] decides. if every filter returns [`None`], the built-in rules apply.
+	/// [`FrameAction::Hide`] is ignored if frame filtering is disabled.
+	///
+	/// ```
+	/// use mayerror::{Config, FrameAction};
+	///
+	/// let config = Config::new()
+	///     .frame_filter(|frame| {
+	///         let name = frame.name()?;
+	///         if name.starts_with("tokio::") || name.starts_with("hyper::") {
+	///             Some(FrameAction::Hide)
+	///         } else if name.starts_with("my_framework::") {
+	///             Some(FrameAction::Dependency)
+	///         } else {
+	///             None
+	///         }
+	///     });
+	/// mayerror::install_with(config);
+	/// ```
+	#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+	pub fn frame_filter<F>(mut self, filter: F) -> Self
+	where
+		F: Fn(&Frame) -> Option<FrameAction> + Send + Sync + 'static,
+	{
+		self.frame_filters.push(FrameFilter::new(filter));
+		self
+	}
+
+	/// crates whose frames are shown as your own code, by their crate name.
+	///
+	/// errors created with `#[derive(MayError)]` always include the crate they are defined in,
+	/// and the files of its workspace outside of `target` and `vendor` if the workspace is known.
+	/// if no crate is known, frames are classified by their symbol name and file path instead.
+	///
+	/// ```
+	/// use mayerror::Config;
+	///
+	/// mayerror::install_with(Config::new().first_party_crates(["my_app", "my_framework"]));
+	/// ```
+	pub fn first_party_crates<I, S>(mut self, crates: I) -> Self
+	where
+		I: IntoIterator<Item = S>,
+		S: Into<String>,
+	{
+		self.first_party_crates
+			.extend(crates.into_iter().map(Into::into));
+		self
+	}
+
+	/// the number of lines shown before and after the line of a source snippet.
+	///
+	/// defaults to 2.
+	pub fn snippet_context(mut self, lines: usize) -> Self {
+		self.lines_before = Some(lines);
+		self.lines_after = Some(lines);
+		self
+	}
+
+	/// the number of lines shown before the line of a source snippet.
+	///
+	/// ```
+	/// use mayerror::Config;
+	///
+	/// mayerror::install_with(Config::new().lines_before(4).lines_after(1));
+	/// ```
+	pub fn lines_before(mut self, lines: usize) -> Self {
+		self.lines_before = Some(lines);
+		self
+	}
+
+	/// the number of lines shown after the line of a source snippet.
+	pub fn lines_after(mut self, lines: usize) -> Self {
+		self.lines_after = Some(lines);
+		self
+	}
+
+	/// shorten the paths of frames: paths in the workspace of the error become relative
+	/// to it, paths in the cargo registry start at the crate, like `tokio-1.38.0/src/lib.rs`,
+	/// and the home directory becomes `~`.
+	///
+	/// defaults to `true`.
+	pub fn shorten_paths(mut self, shorten: bool) -> Self {
+		self.shorten_paths = Some(shorten);
+		self
+	}
+
+	/// a `--remap-path-prefix from=to` the binary was built with.
+	///
+	/// the paths in the binary start with `to`, so source snippets are read from `from` instead.
+	///
+	/// `#[derive(MayError)]` and `embed_sources!` only record paths relative to the workspace,
+	/// so they don't add the build path to the binary.
+	///
+	/// ```
+	/// use mayerror::Config;
+	///
+	/// mayerror::install_with(Config::new().remap_path_prefix("/home/ci/project", "/build"));
+	/// ```
+	pub fn remap_path_prefix(mut self, from: impl Into<PathBuf>, to: impl Into<PathBuf>) -> Self {
+		self.path_remaps.push((from.into(), to.into()));
+		self
+	}
+
+	/// start the backtrace of an error at the frame of its `#[location]`,
+	/// hiding the frames above it.
+	///
+	/// defaults to `false`.
+	pub fn start_at_location(mut self, start: bool) -> Self {
+		self.start_at_location = Some(start);
+		self
+	}
+
+	/// condense backtraces of async code.
+	///
+	/// hides the frames of async executors and of `Future::poll` impls that only forward
+	/// to another future, and labels frames that look like the body of an `async fn` or
+	/// async block as `async`. since closures and futures share the same symbol names,
+	/// closures are labeled as `async` as well.
+	///
+	/// defaults to `false`.
+	pub fn async_mode(mut self, async_mode: bool) -> Self {
+		self.async_mode = Some(async_mode);
+		self
+	}
+
+	/// when to capture the `#[backtrace]` of an error, unless its code
+	/// specifies a [`CapturePolicy`] itself.
+	///
+	/// defaults to [`CapturePolicy::Always`].
+	pub fn capture(mut self, policy: CapturePolicy) -> Self {
+		self.capture = Some(policy);
+		self
+	}
+
+	/// the sources that snippets fall back to when a file can't be read,
+	/// usually created with [`embed_sources!`](crate::embed_sources).
+	#[cfg(feature = "embed-sources")]
+	pub fn embedded_sources(mut self, sources: EmbeddedSources) -> Self {
+		self.embedded_sources = Some(sources);
+		self
+	}
+
+	/// set the [`Theme`].
+	pub fn theme(mut self, theme: Theme) -> Self {
+		self.theme = Some(theme);
+		self
+	}
+
+	/// set the [`Output`] of the panic hook and of [`Report`](crate::Report).
+	///
+	/// by default, the panic hook prints to stdout and [`Report`](crate::Report) prints to stderr.
+	pub fn output(mut self, output: Output) -> Self {
+		self.output = Some(output);
+		self
+	}
+}
+
+static CONFIG: Lazy<RwLock<Config>> = Lazy::new(RwLock::default);
+
+pub(crate) fn set(config: Config) {
+	let mut global = CONFIG.write().unwrap_or_else(|err| err.into_inner());
+	*global = config;
+}
+
+fn get<T>(f: impl FnOnce(&Config) -> T) -> T {
+	let config = CONFIG.read().unwrap_or_else(|err| err.into_inner());
+	f(&config)
+}
+
+#[doc(hidden)]
+pub fn verbosity() -> Verbosity {
+	get(|config| config.verbosity).unwrap_or(*VERBOSITY)
+}
+
+#[doc(hidden)]
+pub fn theme() -> Theme {
+	get(|config| config.theme).unwrap_or_default()
+}
+
+/// whether the verbosity is set by the config, so the environment doesn't change it.
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+pub(crate) fn verbosity_configured() -> bool {
+	get(|config| config.verbosity).is_some()
+}
+
+/// whether frame filtering is set by the config, so the environment doesn't change it.
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+pub(crate) fn frame_filtering_configured() -> bool {
+	get(|config| config.frame_filtering).is_some()
+}
+
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+pub(crate) fn color_bt() -> ColorBt {
+	match get(|config| config.frame_filtering) {
+		Some(true) => ColorBt::Hide,
+		Some(false) => ColorBt::Show,
+		None => *COLOR_BT,
+	}
+}
+
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+pub(crate) fn frame_filters() -> Vec<FrameFilter> {
+	get(|config| config.frame_filters.clone())
+}
+
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+pub(crate) fn first_party_crates() -> Vec<String> {
+	get(|config| config.first_party_crates.clone())
+}
+
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+pub(crate) fn snippet_context() -> (usize, usize) {
+	get(|config| {
+		let before = config.lines_before.unwrap_or(2);
+		let after = config.lines_after.unwrap_or(2);
+		(before, after)
+	})
+}
+
+#[cfg(all(
+	feature = "embed-sources",
+	any(feature = "backtrace", feature = "std-backtrace")
+))]
+pub(crate) fn embedded_sources() -> Option<EmbeddedSources> {
+	get(|config| config.embedded_sources)
+}
+
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+pub(crate) fn shorten_paths() -> bool {
+	get(|config| config.shorten_paths).unwrap_or(true)
+}
+
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+pub(crate) fn path_remaps() -> Vec<(PathBuf, PathBuf)> {
+	get(|config| config.path_remaps.clone())
+}
+
+pub(crate) fn output() -> Option<Output> {
+	get(|config| config.output)
+}
+
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+pub(crate) fn start_at_location() -> bool {
+	get(|config| config.start_at_location).unwrap_or(false)
+}
+
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+pub(crate) fn async_mode() -> bool {
+	get(|config| config.async_mode).unwrap_or(false)
+}
+
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+pub(crate) fn capture_policy() -> CapturePolicy {
+	get(|config| config.capture).unwrap_or(CapturePolicy::Always)
+}
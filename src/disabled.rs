@@ -0,0 +1,49 @@
+use crate::CapturePolicy;
+use std::panic::Location;
+
+/// the placeholder for a backtrace when neither the `backtrace` nor the
+/// `std-backtrace` feature is enabled.
+///
+/// it is zero-sized and never captured, so a `#[backtrace]` field costs nothing,
+/// and libraries can leave it to their users whether backtraces are captured.
+///
+/// ```
+/// use mayerror::Backtrace;
+///
+/// let backtrace = Backtrace::capture();
+/// # #[cfg(not(any(feature = "backtrace", feature = "std-backtrace")))]
+/// assert!(!backtrace.is_captured());
+/// ```
+#[derive(Clone, Copy)]
+pub struct Backtrace {
+	_priv: (),
+}
+
+impl Backtrace {
+	/// doesn't capture anything, as no backtrace feature is enabled.
+	pub fn capture() -> Self {
+		Backtrace { _priv: () }
+	}
+
+	/// always `false`, as no backtrace feature is enabled.
+	pub fn is_captured(&self) -> bool {
+		false
+	}
+
+	/// always `true`, as all placeholders are the same.
+	pub fn ptr_eq(_this: &Backtrace, _other: &Backtrace) -> bool {
+		true
+	}
+}
+
+impl std::fmt::Debug for Backtrace {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str("<disabled>")
+	}
+}
+
+/// the `trace` of the `backtrace` features, which doesn't capture anything.
+#[doc(hidden)]
+pub fn trace(_policy: Option<CapturePolicy>, _location: &'static Location<'static>) -> Backtrace {
+	Backtrace::capture()
+}
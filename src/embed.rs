@@ -0,0 +1,53 @@
+use std::path::Path;
+
+/// the sources of a workspace, compressed into the binary by [`embed_sources!`].
+///
+/// source snippets fall back to these when a file can't be read from its build path,
+/// so they are still shown after the binary was deployed to another machine.
+/// the files are recorded relative to the workspace, and found through the workspace
+/// of the backtrace, so the build path doesn't end up in the binary.
+///
+/// ```
+/// use mayerror::Config;
+///
+/// let config = Config::new().embedded_sources(mayerror::embed_sources!());
+/// mayerror::install_with(config);
+/// ```
+///
+/// [`embed_sources!`]: crate::embed_sources
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedSources {
+	/// the deflate compressed files by their path relative to the workspace root.
+	files: &'static [(&'static str, &'static [u8])],
+}
+
+impl EmbeddedSources {
+	#[doc(hidden)]
+	pub const fn __new(files: &'static [(&'static str, &'static [u8])]) -> Self {
+		EmbeddedSources { files }
+	}
+
+	/// the number of embedded files.
+	pub fn len(&self) -> usize {
+		self.files.len()
+	}
+
+	/// whether no files are embedded.
+	pub fn is_empty(&self) -> bool {
+		self.files.is_empty()
+	}
+
+	/// the content of a file, by its path relative to the workspace.
+	#[cfg_attr(
+		not(any(feature = "backtrace", feature = "std-backtrace")),
+		allow(dead_code)
+	)]
+	pub(crate) fn get(&self, relative: &Path, limit: usize) -> Option<Vec<u8>> {
+		let (_, compressed) = self
+			.files
+			.iter()
+			.find(|(file, _)| Path::new(file) == relative)?;
+
+		miniz_oxide::inflate::decompress_to_vec_with_limit(compressed, limit).ok()
+	}
+}
@@ -1,7 +1,13 @@
-#[cfg(feature = "backtrace")]
-use crate::backtrace::{BacktraceOmitted, PrettyBacktrace, Verbosity, VERBOSITY};
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+use crate::backtrace::{BacktraceOmitted, PrettyBacktrace, Source};
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+use crate::config::{verbosity, Verbosity};
+use crate::{
+	config::{output, theme},
+	Config, Output,
+};
 use owo_colors::OwoColorize;
-use std::panic::PanicHookInfo;
+use std::{fmt::Write, panic::PanicHookInfo};
 
 /// installs the `mayerror` panic hook.
 ///
@@ -17,6 +23,20 @@ pub fn install() {
 	std::panic::set_hook(Box::new(panic_hook));
 }
 
+/// installs the `mayerror` panic hook with a [`Config`].
+///
+/// the config is used by the panic hook and by every `#[derive(MayError)]` error.
+///
+/// ```
+/// use mayerror::{Config, Verbosity};
+///
+/// mayerror::install_with(Config::new().verbosity(Verbosity::Full));
+/// ```
+pub fn install_with(config: Config) {
+	crate::config::set(config);
+	install();
+}
+
 /// the `mayerror` panic hook.
 ///
 /// can be installed via [`mayerror::install`].
@@ -38,23 +58,54 @@ pub fn panic_hook(info: &PanicHookInfo) {
 		"<non string panic payload>"
 	};
 
-	println!("{}", "The application panicked.".red());
-	println!("Message: {}", payload.cyan());
+	// captured before the snippet, which is read from the workspace found in the backtrace
+	#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+	let backtrace = (verbosity() >= Verbosity::Medium).then(|| {
+		let backtrace = PrettyBacktrace::new(&crate::Backtrace::capture());
+		let workspace = info
+			.location()
+			.and_then(|location| backtrace.workspace_at(location));
+		backtrace.in_workspace(workspace)
+	});
+
+	let theme = theme();
+	let mut message = String::new();
+	let _ = writeln!(
+		message,
+		"{}",
+		"The application panicked.".style(theme.panic_header)
+	);
+	let _ = writeln!(message, "Message: {}", payload.style(theme.panic_message));
 
 	if let Some(location) = info.location() {
-		print!("Location: {}", location.magenta());
+		let _ = write!(
+			message,
+			"Location: {}",
+			location.style(theme.panic_location)
+		);
+
+		#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+		if verbosity() >= Verbosity::Full {
+			let workspace = backtrace.as_ref().and_then(PrettyBacktrace::workspace);
+			let source = Source::location(location).workspace(workspace);
+			let _ = write!(message, "{}", source);
+		}
 	} else {
-		print!("Location: {}", "<unknown>".magenta());
+		let _ = write!(
+			message,
+			"Location: {}",
+			"<unknown>".style(theme.panic_location)
+		);
 	}
 
-	#[cfg(feature = "backtrace")]
-	if *VERBOSITY >= Verbosity::Medium {
-		let backtrace = ::backtrace::Backtrace::new();
-		print!("\n\n{}", PrettyBacktrace(&backtrace));
+	#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+	if let Some(backtrace) = &backtrace {
+		let _ = write!(message, "\n\n{}", backtrace);
 	}
-	#[cfg(not(feature = "backtrace"))]
-	println!();
 
-	#[cfg(feature = "backtrace")]
-	println!("{}", BacktraceOmitted);
+	#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+	let _ = write!(message, "{}", BacktraceOmitted);
+	message.push('\n');
+
+	output().unwrap_or(Output::Stdout).write(&message);
 }
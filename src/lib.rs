@@ -11,6 +11,9 @@
 //! }
 //! ```
 //!
+//! the error code can also implement [`mayerror::Code`](Code), which you can derive.
+//! it lets you attach metadata to the variants, like `#[mayerror(exit_code = 78)]`.
+//!
 //! then you can use that error code in a `MayError` struct with the `#[code]` attribute
 //!
 //! ```
@@ -97,22 +100,60 @@
 //! }
 //! ```
 
-pub use self::install::{install, panic_hook};
+pub use self::canonical::Canonical;
+#[cfg(feature = "tonic")]
+pub use self::canonical::STATUS_METADATA_KEY;
+pub use self::code::{Code, MayError};
+pub use self::config::{CapturePolicy, Config, Output, Verbosity};
+#[cfg(feature = "embed-sources")]
+pub use self::embed::EmbeddedSources;
+pub use self::install::{install, install_with, panic_hook};
+pub use self::problem::Problem;
+pub use self::report::Report;
+pub use self::retry::is_transient;
+pub use self::theme::Theme;
 pub use mayerror_derive::*;
+pub use owo_colors::Style;
 
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+pub use self::backtrace::{Backtrace, Frame, FrameAction, FrameKind, Frames};
 #[cfg(feature = "backtrace")]
-pub use ::backtrace::Backtrace;
+pub use self::backtrace::{ParseRawBacktraceError, RawBacktrace};
+#[cfg(not(any(feature = "backtrace", feature = "std-backtrace")))]
+pub use self::disabled::Backtrace;
 
-#[cfg(feature = "backtrace")]
+#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
 mod backtrace;
+mod canonical;
 mod chain;
+mod code;
+mod config;
+#[cfg(not(any(feature = "backtrace", feature = "std-backtrace")))]
+mod disabled;
+#[cfg(feature = "embed-sources")]
+mod embed;
 mod install;
+mod problem;
+mod report;
+mod retry;
+mod theme;
 
 #[doc(hidden)]
 pub mod __private {
-	#[cfg(feature = "backtrace")]
+	#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
 	pub use super::backtrace::*;
+	#[cfg(feature = "tonic")]
+	pub use super::canonical::tonic_status;
 	pub use super::chain::*;
+	pub use super::code::{AsLocation, CodeOf, LocationOf, NotLocation, WithCode, WithoutCode};
+	pub use super::config::{theme, verbosity};
+	#[cfg(not(any(feature = "backtrace", feature = "std-backtrace")))]
+	pub use super::disabled::trace;
+	pub use super::theme::styles;
 
+	pub use crate::__location as location;
+	pub use crate::__metadata as metadata;
 	pub use owo_colors::OwoColorize;
+	#[cfg(feature = "tonic")]
+	pub use tonic;
 }
@@ -0,0 +1,184 @@
+use crate::MayError;
+use std::fmt::{Display, Write};
+
+/// renders an error as an [RFC 9457](https://www.rfc-editor.org/rfc/rfc9457)
+/// `application/problem+json` document.
+///
+/// the `type` is the [`Code::id`](crate::Code::id) appended to the [`Problem::type_base`],
+/// or `about:blank` if no base is set, and the `instance` is derived from the [`Code::id`](crate::Code::id).
+/// the `#[location]` and `#[backtrace]` of the error are only included in [`Problem::debug`] mode.
+///
+/// ```
+/// use mayerror::{MayError, Problem};
+///
+/// #[derive(Debug, thiserror::Error, mayerror::Code)]
+/// pub enum ErrorCode {
+///     #[error("user {0} not found")]
+///     #[mayerror(status = 404)]
+///     UserNotFound(u32),
+/// }
+///
+/// #[derive(MayError)]
+/// pub struct Error {
+///     #[code]
+///     code: ErrorCode,
+/// }
+///
+/// let error = Error::from(ErrorCode::UserNotFound(7));
+/// assert_eq!(error.status(), 404);
+///
+/// let problem = Problem::new(&error).type_base("https://example.com/errors/");
+/// assert_eq!(
+///     problem.to_string(),
+///     r#"{"type":"https://example.com/errors/user-not-found","title":"Not Found","status":404,"detail":"user 7 not found","instance":"urn:mayerror:user-not-found"}"#,
+/// );
+/// ```
+pub struct Problem<'a, E> {
+	error: &'a E,
+	type_base: Option<&'a str>,
+	debug: bool,
+}
+
+impl<'a, E> Problem<'a, E>
+where
+	E: MayError,
+	E::Code: Display,
+{
+	/// the media type of the rendered document.
+	pub const CONTENT_TYPE: &'static str = "application/problem+json";
+
+	/// a problem document for the error.
+	pub fn new(error: &'a E) -> Self {
+		Problem {
+			error,
+			type_base: None,
+			debug: false,
+		}
+	}
+
+	/// the uri the [`Code::id`](crate::Code::id) gets appended to, to create the `type` of the problem.
+	pub fn type_base(mut self, type_base: &'a str) -> Self {
+		self.type_base = Some(type_base);
+		self
+	}
+
+	/// include the `#[location]` and `#[backtrace]` of the error.
+	///
+	/// this leaks implementation details, so it should only be enabled in development.
+	pub fn debug(mut self, debug: bool) -> Self {
+		self.debug = debug;
+		self
+	}
+}
+
+impl<E> Display for Problem<'_, E>
+where
+	E: MayError,
+	E::Code: Display,
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let code = self.error.code();
+		let status = self.error.status();
+		let detail = code.to_string();
+
+		f.write_str("{\"type\":")?;
+		match self.type_base {
+			Some(base) => json_str(f, &format!("{}{}", base, self.error.metadata().id()))?,
+			None => json_str(f, "about:blank")?,
+		}
+
+		f.write_str(",\"title\":")?;
+		json_str(f, reason(status).unwrap_or(&detail))?;
+
+		write!(f, ",\"status\":{}", status)?;
+
+		f.write_str(",\"detail\":")?;
+		json_str(f, &detail)?;
+
+		f.write_str(",\"instance\":")?;
+		json_str(f, &format!("urn:mayerror:{}", self.error.metadata().id()))?;
+
+		if self.debug {
+			if let Some(location) = self.error.location() {
+				f.write_str(",\"location\":")?;
+				json_str(f, &location.to_string())?;
+			}
+
+			#[cfg(any(feature = "backtrace", feature = "std-backtrace"))]
+			if let Some(backtrace) = self.error.backtrace() {
+				f.write_str(",\"backtrace\":[")?;
+				for (idx, frame) in crate::backtrace::plain_frames(backtrace).iter().enumerate() {
+					if idx != 0 {
+						f.write_char(',')?;
+					}
+					json_str(f, frame)?;
+				}
+				f.write_char(']')?;
+			}
+		}
+
+		f.write_char('}')
+	}
+}
+
+fn json_str(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+	f.write_char('"')?;
+	for ch in s.chars() {
+		match ch {
+			'"' => f.write_str("\\\"")?,
+			'\\' => f.write_str("\\\\")?,
+			'\n' => f.write_str("\\n")?,
+			'\r' => f.write_str("\\r")?,
+			'\t' => f.write_str("\\t")?,
+			ch if ch.is_control() => write!(f, "\\u{:04x}", ch as u32)?,
+			ch => f.write_char(ch)?,
+		}
+	}
+	f.write_char('"')
+}
+
+fn reason(status: u16) -> Option<&'static str> {
+	let reason = match status {
+		400 => "Bad Request",
+		401 => "Unauthorized",
+		402 => "Payment Required",
+		403 => "Forbidden",
+		404 => "Not Found",
+		405 => "Method Not Allowed",
+		406 => "Not Acceptable",
+		407 => "Proxy Authentication Required",
+		408 => "Request Timeout",
+		409 => "Conflict",
+		410 => "Gone",
+		411 => "Length Required",
+		412 => "Precondition Failed",
+		413 => "Content Too Large",
+		414 => "URI Too Long",
+		415 => "Unsupported Media Type",
+		416 => "Range Not Satisfiable",
+		417 => "Expectation Failed",
+		421 => "Misdirected Request",
+		422 => "Unprocessable Content",
+		423 => "Locked",
+		424 => "Failed Dependency",
+		425 => "Too Early",
+		426 => "Upgrade Required",
+		428 => "Precondition Required",
+		429 => "Too Many Requests",
+		431 => "Request Header Fields Too Large",
+		451 => "Unavailable For Legal Reasons",
+		500 => "Internal Server Error",
+		501 => "Not Implemented",
+		502 => "Bad Gateway",
+		503 => "Service Unavailable",
+		504 => "Gateway Timeout",
+		505 => "HTTP Version Not Supported",
+		506 => "Variant Also Negotiates",
+		507 => "Insufficient Storage",
+		508 => "Loop Detected",
+		511 => "Network Authentication Required",
+		_ => return None,
+	};
+
+	Some(reason)
+}
@@ -0,0 +1,53 @@
+use crate::{config::output, MayError, Output};
+use std::process::{ExitCode, Termination};
+
+/// a [`Termination`] wrapper to return a [`MayError`] from `main`.
+///
+/// if the result is an error, it prints the pretty report to stderr (or the configured [`Output`])
+/// and exits with the [`MayError::exit_code`] of the error.
+///
+/// ```no_run
+/// use mayerror::{MayError, Report};
+///
+/// #[derive(Debug, thiserror::Error, mayerror::Code)]
+/// pub enum ErrorCode {
+///     #[error("config file empty")]
+///     #[mayerror(exit_code = 78)]
+///     EmptyFile,
+/// }
+///
+/// #[derive(MayError)]
+/// pub struct Error {
+///     #[code]
+///     code: ErrorCode,
+/// }
+///
+/// fn run() -> Result<(), Error> {
+///     let () = Err(ErrorCode::EmptyFile)?;
+///     Ok(())
+/// }
+///
+/// fn main() -> Report<Error> {
+///     Report::from(run())
+/// }
+/// ```
+pub struct Report<E>(pub Result<(), E>);
+
+impl<E> From<Result<(), E>> for Report<E> {
+	fn from(value: Result<(), E>) -> Self {
+		Report(value)
+	}
+}
+
+impl<E: MayError> Termination for Report<E> {
+	fn report(self) -> ExitCode {
+		match self.0 {
+			Ok(()) => ExitCode::SUCCESS,
+			Err(err) => {
+				let message = format!("{:?}\n", err);
+				output().unwrap_or(Output::Stderr).write(&message);
+				err.exit_code()
+			}
+		}
+	}
+}
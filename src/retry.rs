@@ -0,0 +1,35 @@
+use crate::__private::Chain;
+use std::io::ErrorKind;
+
+/// whether any error in the source chain is a transient [`std::io::Error`].
+///
+/// an io error is considered transient if retrying the operation might succeed,
+/// e.g. [`ErrorKind::TimedOut`] or [`ErrorKind::ConnectionReset`].
+///
+/// this is what `#[mayerror(retryable = source)]` uses.
+///
+/// ```
+/// use std::io::{Error, ErrorKind};
+///
+/// assert!(mayerror::is_transient(&Error::from(ErrorKind::TimedOut)));
+/// assert!(!mayerror::is_transient(&Error::from(ErrorKind::NotFound)));
+/// ```
+pub fn is_transient(error: &(dyn std::error::Error + 'static)) -> bool {
+	Chain::new(error).any(|error| {
+		let Some(error) = error.downcast_ref::<std::io::Error>() else {
+			return false;
+		};
+
+		matches!(
+			error.kind(),
+			ErrorKind::TimedOut
+				| ErrorKind::Interrupted
+				| ErrorKind::WouldBlock
+				| ErrorKind::ConnectionReset
+				| ErrorKind::ConnectionAborted
+				| ErrorKind::ConnectionRefused
+				| ErrorKind::BrokenPipe
+				| ErrorKind::UnexpectedEof
+		)
+	})
+}
@@ -0,0 +1,249 @@
+use owo_colors::Style;
+
+/// the colors used to print errors, panics and backtraces.
+///
+/// ```
+/// use mayerror::{Style, Theme};
+///
+/// let theme = Theme::dark().error(Style::new().bright_red().bold());
+/// ```
+///
+/// the colors are always written, even if the output isn't a terminal or `NO_COLOR` is set.
+/// only a plain theme like [`Theme::new`] turns them off, including the syntax highlighting
+/// of source snippets.
+///
+/// ```
+/// use mayerror::{Config, Theme};
+///
+/// let theme = match std::env::var_os("NO_COLOR") {
+///     Some(_) => Theme::new(),
+///     None => Theme::dark(),
+/// };
+/// mayerror::install_with(Config::new().theme(theme));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+	pub(crate) error: Style,
+	pub(crate) source: Style,
+	pub(crate) location: Style,
+	pub(crate) panic_header: Style,
+	pub(crate) panic_message: Style,
+	pub(crate) panic_location: Style,
+	pub(crate) crate_code: Style,
+	pub(crate) dependency_code: Style,
+	pub(crate) location_frame: Style,
+	pub(crate) async_marker: Style,
+	pub(crate) inlined_marker: Style,
+	pub(crate) file: Style,
+	pub(crate) line_number: Style,
+	pub(crate) hidden_frames: Style,
+	pub(crate) active_line: Style,
+	pub(crate) source_unavailable: Style,
+	pub(crate) syntax_keyword: Style,
+	pub(crate) syntax_string: Style,
+	pub(crate) syntax_comment: Style,
+	pub(crate) syntax_number: Style,
+}
+
+impl Default for Theme {
+	fn default() -> Self {
+		Theme::dark()
+	}
+}
+
+impl Theme {
+	/// a theme without any colors.
+	pub fn new() -> Self {
+		Theme {
+			error: Style::new(),
+			source: Style::new(),
+			location: Style::new(),
+			panic_header: Style::new(),
+			panic_message: Style::new(),
+			panic_location: Style::new(),
+			crate_code: Style::new(),
+			dependency_code: Style::new(),
+			location_frame: Style::new(),
+			async_marker: Style::new(),
+			inlined_marker: Style::new(),
+			file: Style::new(),
+			line_number: Style::new(),
+			hidden_frames: Style::new(),
+			active_line: Style::new(),
+			source_unavailable: Style::new(),
+			syntax_keyword: Style::new(),
+			syntax_string: Style::new(),
+			syntax_comment: Style::new(),
+			syntax_number: Style::new(),
+		}
+	}
+
+	/// the default theme, for dark terminal backgrounds.
+	pub fn dark() -> Self {
+		Theme {
+			error: Style::new().red(),
+			source: Style::new().magenta(),
+			location: Style::new().cyan(),
+			panic_header: Style::new().red(),
+			panic_message: Style::new().cyan(),
+			panic_location: Style::new().magenta(),
+			crate_code: Style::new().red(),
+			dependency_code: Style::new().green(),
+			location_frame: Style::new().cyan().bold(),
+			async_marker: Style::new().blue(),
+			inlined_marker: Style::new().dimmed(),
+			file: Style::new().purple(),
+			line_number: Style::new().purple(),
+			hidden_frames: Style::new().cyan(),
+			active_line: Style::new().bold(),
+			source_unavailable: Style::new().dimmed(),
+			syntax_keyword: Style::new().blue(),
+			syntax_string: Style::new().green(),
+			syntax_comment: Style::new().dimmed(),
+			syntax_number: Style::new().yellow(),
+		}
+	}
+
+	/// the style of the error code.
+	pub fn error(mut self, style: Style) -> Self {
+		self.error = style;
+		self
+	}
+
+	/// the style of the entries in the source chain.
+	pub fn source(mut self, style: Style) -> Self {
+		self.source = style;
+		self
+	}
+
+	/// the style of the `#[location]` of an error.
+	pub fn location(mut self, style: Style) -> Self {
+		self.location = style;
+		self
+	}
+
+	/// the style of the "The application panicked." header.
+	pub fn panic_header(mut self, style: Style) -> Self {
+		self.panic_header = style;
+		self
+	}
+
+	/// the style of the panic message.
+	pub fn panic_message(mut self, style: Style) -> Self {
+		self.panic_message = style;
+		self
+	}
+
+	/// the style of the panic location.
+	pub fn panic_location(mut self, style: Style) -> Self {
+		self.panic_location = style;
+		self
+	}
+
+	/// the style of frames from your own code.
+	pub fn crate_code(mut self, style: Style) -> Self {
+		self.crate_code = style;
+		self
+	}
+
+	/// the style of frames from dependencies and the standard library.
+	pub fn dependency_code(mut self, style: Style) -> Self {
+		self.dependency_code = style;
+		self
+	}
+
+	/// the style of the frame that matches the `#[location]` of an error.
+	pub fn location_frame(mut self, style: Style) -> Self {
+		self.location_frame = style;
+		self
+	}
+
+	/// the style of the `async` label in async mode.
+	pub fn async_marker(mut self, style: Style) -> Self {
+		self.async_marker = style;
+		self
+	}
+
+	/// the style of the `inlined` label of frames that were inlined into their caller.
+	pub fn inlined_marker(mut self, style: Style) -> Self {
+		self.inlined_marker = style;
+		self
+	}
+
+	/// the style of the file path of a frame.
+	pub fn file(mut self, style: Style) -> Self {
+		self.file = style;
+		self
+	}
+
+	/// the style of the line number of a frame.
+	pub fn line_number(mut self, style: Style) -> Self {
+		self.line_number = style;
+		self
+	}
+
+	/// the style of the "frames hidden" marker.
+	pub fn hidden_frames(mut self, style: Style) -> Self {
+		self.hidden_frames = style;
+		self
+	}
+
+	/// the style of the highlighted line in a source snippet.
+	pub fn active_line(mut self, style: Style) -> Self {
+		self.active_line = style;
+		self
+	}
+
+	/// the style of the "source unavailable" note shown instead of a source snippet.
+	pub fn source_unavailable(mut self, style: Style) -> Self {
+		self.source_unavailable = style;
+		self
+	}
+
+	/// the style of keywords in a source snippet.
+	///
+	/// source snippets are only highlighted if one of the `syntax_` styles isn't plain,
+	/// independent of the terminal or of `NO_COLOR`.
+	pub fn syntax_keyword(mut self, style: Style) -> Self {
+		self.syntax_keyword = style;
+		self
+	}
+
+	/// the style of string and character literals in a source snippet.
+	pub fn syntax_string(mut self, style: Style) -> Self {
+		self.syntax_string = style;
+		self
+	}
+
+	/// the style of comments in a source snippet.
+	pub fn syntax_comment(mut self, style: Style) -> Self {
+		self.syntax_comment = style;
+		self
+	}
+
+	/// the style of number literals in a source snippet.
+	pub fn syntax_number(mut self, style: Style) -> Self {
+		self.syntax_number = style;
+		self
+	}
+}
+
+/// the styles read by the `Debug` impl of `#[derive(MayError)]`,
+/// which can't access the fields of the theme.
+#[doc(hidden)]
+pub mod styles {
+	use super::Theme;
+	use owo_colors::Style;
+
+	pub fn error(theme: &Theme) -> Style {
+		theme.error
+	}
+
+	pub fn source(theme: &Theme) -> Style {
+		theme.source
+	}
+
+	pub fn location(theme: &Theme) -> Style {
+		theme.location
+	}
+}